@@ -0,0 +1,68 @@
+use std::{
+    cell::RefCell,
+    fmt::{Debug, Display},
+    rc::Rc,
+};
+
+use crate::{list, literal::Literal, parser::Expr};
+
+/// A mutable reference, made by `ref`. Copies of a cell share its value, so a closure
+/// capturing one sees what is later stored in it.
+#[derive(Clone)]
+pub struct Cell(Rc<RefCell<Expr>>);
+
+impl Cell {
+    pub fn new(value: Expr) -> Self {
+        Cell(Rc::new(RefCell::new(value)))
+    }
+
+    pub fn get(&self) -> Expr {
+        self.0.borrow().clone()
+    }
+
+    pub fn set(&self, value: Expr) {
+        *self.0.borrow_mut() = value;
+    }
+
+    pub fn ptr_eq(&self, other: &Cell) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+
+    /// The value of a cell nothing else refers to, leaving `Φ` in its place.
+    pub fn take(&mut self) -> Option<Expr> {
+        match Rc::get_mut(&mut self.0) {
+            Some(value) => Some(std::mem::replace(
+                value.get_mut(),
+                Expr::Literal(Literal::Nil),
+            )),
+            None => None,
+        }
+    }
+}
+
+impl Drop for Cell {
+    // Dropping cells nested in cells recursively would overflow the stack.
+    fn drop(&mut self) {
+        if let Some(value) = self.take() {
+            list::drop_nested(value);
+        }
+    }
+}
+
+impl Debug for Cell {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // A cell may contain itself.
+        f.debug_tuple("Cell").finish_non_exhaustive()
+    }
+}
+
+impl Display for Cell {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // The cell stays borrowed while its value is printed, so a cell containing itself
+        // prints as `(ref ...)` the second time.
+        match self.0.try_borrow_mut() {
+            Ok(value) => f.write_fmt(format_args!("(ref {})", *value)),
+            Err(_) => f.write_str("(ref ...)"),
+        }
+    }
+}
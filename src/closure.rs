@@ -0,0 +1,183 @@
+use std::{
+    collections::HashSet,
+    fmt::{Debug, Display},
+};
+
+use crate::{error::EvalError, frame::Env, keywords::Keyword, parser::Expr};
+
+/// Separates the required parameters of a λ from its rest parameter, `(a b . rest)`.
+pub const REST_SEPARATOR: &str = ".";
+
+/// What a λ binds its arguments to.
+#[derive(Debug, Clone)]
+pub enum Parameters {
+    /// A variable, literal or `_`, taking one argument at a time.
+    Single(Expr),
+    /// `(a b . rest)`, taking all of its arguments at once.
+    List {
+        required: Vec<Expr>,
+        rest: Option<Expr>,
+    },
+}
+
+impl Parameters {
+    pub fn new(parameter: &Expr) -> Result<Self, EvalError> {
+        let invalid = || EvalError::failure(format!("Invalid lambda (λ) argument: {}", parameter));
+
+        match parameter {
+            Expr::Var { .. } | Expr::Literal(_) | Expr::Keyword(Keyword::Ignore) => {
+                Ok(Parameters::Single(parameter.clone()))
+            }
+            Expr::List(parameters) if parameters.is_empty() => Ok(Parameters::List {
+                required: vec![],
+                rest: None,
+            }),
+            Expr::Expr {
+                operator, operands, ..
+            } => {
+                let mut required = vec![];
+                let mut rest = None;
+                let mut names = HashSet::new();
+                let mut parameters = std::iter::once(operator.as_ref()).chain(operands);
+
+                while let Some(parameter) = parameters.next() {
+                    match parameter {
+                        Expr::Var { name, .. } if name == REST_SEPARATOR => {
+                            match (parameters.next(), parameters.next()) {
+                                (Some(parameter @ Expr::Var { name, .. }), None)
+                                    if name != REST_SEPARATOR =>
+                                {
+                                    rest = Some(parameter.clone())
+                                }
+                                _ => return Err(invalid()),
+                            }
+                        }
+                        Expr::Var { .. } | Expr::Keyword(Keyword::Ignore) => {
+                            required.push(parameter.clone())
+                        }
+                        parameter => {
+                            return Err(EvalError::failure(format!(
+                                "lambda (λ) parameter must be a symbol, not {}",
+                                parameter
+                            )))
+                        }
+                    }
+                }
+
+                // `_` ignores an argument, so it may be repeated.
+                for parameter in required.iter().chain(&rest) {
+                    if let Expr::Var { name, .. } = parameter {
+                        if !names.insert(name) {
+                            return Err(EvalError::failure(format!(
+                                "Duplicate name '{}' in lambda (λ) parameters",
+                                name
+                            )));
+                        }
+                    }
+                }
+
+                Ok(Parameters::List { required, rest })
+            }
+            _ => Err(invalid()),
+        }
+    }
+
+    /// Whether the parameters take no arguments at all, as `()` does.
+    pub fn is_empty(&self) -> bool {
+        matches!(self, Parameters::List { required, rest: None } if required.is_empty())
+    }
+
+    /// The number of arguments the parameters take, as shown in arity errors.
+    pub fn arity(&self) -> String {
+        match self {
+            Parameters::Single(_) => "1".to_string(),
+            Parameters::List {
+                required,
+                rest: None,
+            } => format!("{}", required.len()),
+            Parameters::List {
+                required,
+                rest: Some(_),
+            } => format!("{} or more", required.len()),
+        }
+    }
+}
+
+impl Display for Parameters {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Parameters::Single(parameter) => f.write_fmt(format_args!("{}", parameter)),
+            Parameters::List { required, rest } => {
+                let mut parameters = required
+                    .iter()
+                    .map(|parameter| format!("{}", parameter))
+                    .collect::<Vec<String>>();
+
+                if let Some(rest) = rest {
+                    parameters.push(REST_SEPARATOR.to_string());
+                    parameters.push(format!("{}", rest));
+                }
+
+                f.write_fmt(format_args!("({})", parameters.join(" ")))
+            }
+        }
+    }
+}
+
+/// A λ together with the frame it was created in.
+#[derive(Clone)]
+pub struct Closure {
+    pub parameters: Parameters,
+    pub body: Expr,
+    pub env: Env,
+    /// The name the closure was first bound to by δ, used in error messages.
+    pub name: Option<String>,
+}
+
+impl Closure {
+    /// Describes the closure in error messages.
+    pub fn describe(&self) -> String {
+        match &self.name {
+            Some(name) => format!("lambda (λ) '{}'", name),
+            None => "lambda (λ)".to_string(),
+        }
+    }
+}
+
+impl Debug for Closure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // The captured frame usually contains the closure itself.
+        f.debug_struct("Closure")
+            .field("parameters", &self.parameters)
+            .field("body", &self.body)
+            .field("name", &self.name)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Display for Closure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // A body of several forms is kept in `begin`, but printed as it was written.
+        let body = match &self.body {
+            Expr::Expr {
+                operator, operands, ..
+            } if matches!(operator.as_ref(), Expr::Keyword(Keyword::Begin))
+                && operands.len() > 1 =>
+            {
+                operands
+                    .iter()
+                    .map(|form| format!("{}", form))
+                    .collect::<Vec<String>>()
+                    .join(" ")
+            }
+            body => format!("{}", body),
+        };
+
+        f.write_fmt(format_args!(
+            "({} {} {})",
+            Keyword::Lambda,
+            self.parameters,
+            body
+        ))
+    }
+}
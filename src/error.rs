@@ -0,0 +1,153 @@
+use std::fmt::Display;
+
+use crate::{parser::Expr, position::Span};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ErrorKind {
+    Parse(String),
+    UnboundVariable(String),
+    ArityMismatch {
+        function: String,
+        expected: String,
+        received: usize,
+    },
+    PatternMismatch {
+        function: String,
+        expected: String,
+        received: String,
+    },
+    TypeError {
+        function: String,
+        expected: String,
+        received: String,
+    },
+    IndexOutOfRange {
+        function: String,
+        index: String,
+        collection: String,
+        length: usize,
+    },
+    NoMatch(String),
+    Failure(String),
+}
+
+impl Display for ErrorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ErrorKind::Parse(message) => f.write_fmt(format_args!("{}", message)),
+            ErrorKind::UnboundVariable(name) => {
+                f.write_fmt(format_args!("Variable '{}' is not defined", name))
+            }
+            ErrorKind::ArityMismatch {
+                function,
+                expected,
+                received,
+            } => f.write_fmt(format_args!(
+                "{} expected {} arguments but {} arguments were provided.",
+                function, expected, received
+            )),
+            ErrorKind::PatternMismatch {
+                function,
+                expected,
+                received,
+            } => f.write_fmt(format_args!(
+                "{} expected '{}' but received '{}'",
+                function, expected, received
+            )),
+            ErrorKind::TypeError {
+                function,
+                expected,
+                received,
+            } => f.write_fmt(format_args!(
+                "{} expected {} but received '{}'",
+                function, expected, received
+            )),
+            ErrorKind::IndexOutOfRange {
+                function,
+                index,
+                collection,
+                length,
+            } => f.write_fmt(format_args!(
+                "{} index {} is out of range for {} of length {}",
+                function, index, collection, length
+            )),
+            ErrorKind::NoMatch(scrutinee) => {
+                f.write_fmt(format_args!("match has no clause matching '{}'", scrutinee))
+            }
+            ErrorKind::Failure(message) => f.write_fmt(format_args!("{}", message)),
+        }
+    }
+}
+
+/// An error raised while lexing, parsing or evaluating, with the source span it refers to.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EvalError {
+    pub kind: ErrorKind,
+    pub span: Option<Span>,
+}
+
+impl EvalError {
+    pub fn new(kind: ErrorKind, span: Option<Span>) -> Self {
+        Self { kind, span }
+    }
+
+    pub fn failure(message: String) -> Self {
+        Self::new(ErrorKind::Failure(message), None)
+    }
+
+    pub fn parse(message: String, span: Option<Span>) -> Self {
+        Self::new(ErrorKind::Parse(message), span)
+    }
+
+    pub fn arity(function: String, expected: String, received: usize) -> Self {
+        Self::new(
+            ErrorKind::ArityMismatch {
+                function,
+                expected,
+                received,
+            },
+            None,
+        )
+    }
+
+    pub fn type_error(function: String, expected: &str, received: &Expr) -> Self {
+        Self::new(
+            ErrorKind::TypeError {
+                function,
+                expected: expected.to_string(),
+                received: format!("{}", received),
+            },
+            None,
+        )
+    }
+
+    pub fn out_of_range(function: String, index: &Expr, collection: &str, length: usize) -> Self {
+        Self::new(
+            ErrorKind::IndexOutOfRange {
+                function,
+                index: format!("{}", index),
+                collection: collection.to_string(),
+                length,
+            },
+            None,
+        )
+    }
+
+    /// Attaches `span` unless the error already points somewhere more precise.
+    pub fn at(mut self, span: Option<Span>) -> Self {
+        self.span = self.span.or(span);
+
+        self
+    }
+}
+
+impl Display for EvalError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.span {
+            Some(span) => f.write_fmt(format_args!("{}: {}", span, self.kind)),
+            None => f.write_fmt(format_args!("{}", self.kind)),
+        }
+    }
+}
+
+impl std::error::Error for EvalError {}
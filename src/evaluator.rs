@@ -1,368 +1,3354 @@
-use std::collections::{HashMap, VecDeque};
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    rc::Rc,
+};
 
 use crate::{
-    frame::Frame,
+    closure::{Closure, Parameters, REST_SEPARATOR},
+    error::{ErrorKind, EvalError},
+    external::{self, External, IntoExternal},
+    frame::{Cycles, Env, Frame},
     keywords::Keyword,
-    parser::{Expr, ParseResult},
+    list::List,
+    literal::Literal,
+    macros::Macro,
+    map::Map,
+    parser::Expr,
+    pattern,
+    primitive::Primitive,
 };
 
+/// Binder of the λ produced when `->` composes functions.
+const ARROW_ARGUMENT: &str = "->x";
+
+/// Introduces the guard of a `match` clause, `(pattern when guard body)`.
+const GUARD: &str = "when";
+
+/// The test of a `cond` clause that always holds, `(else body)`.
+const ELSE: &str = "else";
+
+/// Whether an evaluated expression can be applied to an argument.
+fn is_function(expr: &Expr) -> bool {
+    match expr {
+        Expr::Expr { operator, .. } => {
+            matches!(operator.as_ref(), Expr::Keyword(Keyword::External))
+        }
+        Expr::Closure(_) | Expr::Primitive(_) | Expr::Keyword(Keyword::Id) => true,
+        _ => false,
+    }
+}
+
+/// Names an anonymous closure after the variable δ binds it to.
+fn named(value: Expr, name: &str) -> Expr {
+    match value {
+        Expr::Closure(closure) if closure.name.is_none() => Expr::Closure(Rc::new(Closure {
+            name: Some(name.to_string()),
+            ..closure.as_ref().clone()
+        })),
+        value => value,
+    }
+}
+
+/// The body made of `forms`, wrapped in `begin` unless there is exactly one.
+fn body(forms: &[Expr]) -> Expr {
+    match forms {
+        [form] => form.clone(),
+        forms => Expr::Expr {
+            operator: Box::new(Expr::Keyword(Keyword::Begin)),
+            operands: forms.to_vec(),
+            span: None,
+        },
+    }
+}
+
+/// Splits the bindings of `let`, `let*` or `letrec`, `((name value) ...)`, into names and
+/// values.
+fn let_bindings(bindings: &Expr) -> Option<Vec<(String, Expr)>> {
+    let bindings = match bindings {
+        Expr::List(bindings) => bindings,
+        _ => return None,
+    };
+
+    bindings
+        .iter()
+        .map(|binding| match binding {
+            Expr::List(parts) => match &parts.iter().collect::<Vec<&Expr>>()[..] {
+                [Expr::Var { name, .. }, value] if name != REST_SEPARATOR => {
+                    Some((name.clone(), (*value).clone()))
+                }
+                _ => None,
+            },
+            _ => None,
+        })
+        .collect()
+}
+
+/// The operands of `let`, `let*` or `letrec` with the given bindings and body.
+fn let_operands(bindings: Vec<(String, Expr)>, body: Vec<Expr>) -> Vec<Expr> {
+    let bindings = bindings
+        .into_iter()
+        .map(|(name, value)| {
+            Expr::List(
+                [Expr::Var { name, span: None }, value]
+                    .into_iter()
+                    .collect(),
+            )
+        })
+        .collect();
+
+    std::iter::once(Expr::List(bindings)).chain(body).collect()
+}
+
+/// The names bound by `bindings`, as a binder.
+fn let_binder(bindings: &[(String, Expr)]) -> Expr {
+    Expr::List(
+        bindings
+            .iter()
+            .map(|(name, _)| Expr::Var {
+                name: name.clone(),
+                span: None,
+            })
+            .collect(),
+    )
+}
+
+/// Whether a binder, such as a λ parameter list or a `match` pattern, binds `name`.
+fn binds(binder: &Expr, name: &str) -> bool {
+    match binder {
+        Expr::Var { name: v_name, .. } => v_name == name && v_name != REST_SEPARATOR,
+        Expr::Expr {
+            operator, operands, ..
+        } => binds(operator, name) || operands.iter().any(|operand| binds(operand, name)),
+        // Quoted parts of patterns, such as `'tag`, are matched rather than bound.
+        Expr::List(_) if prefixed(binder).is_some() => false,
+        Expr::List(items) => items.iter().any(|item| binds(item, name)),
+        _ => false,
+    }
+}
+
+/// The variables bound by a binder, in order.
+fn bound_names(binder: &Expr) -> Vec<String> {
+    match binder {
+        Expr::Var { name, .. } if name != REST_SEPARATOR => vec![name.clone()],
+        Expr::Expr {
+            operator, operands, ..
+        } => std::iter::once(operator.as_ref())
+            .chain(operands)
+            .flat_map(bound_names)
+            .collect(),
+        Expr::List(_) if prefixed(binder).is_some() => vec![],
+        Expr::List(items) => items.iter().flat_map(bound_names).collect(),
+        _ => vec![],
+    }
+}
+
+/// Collects the names of all the variables in `ast`, bound, free or quoted.
+fn variables(ast: &Expr, names: &mut HashSet<String>) {
+    match ast {
+        Expr::Var { name, .. } => {
+            names.insert(name.clone());
+        }
+        Expr::Expr {
+            operator, operands, ..
+        } => std::iter::once(operator.as_ref())
+            .chain(operands)
+            .for_each(|expr| variables(expr, names)),
+        Expr::List(items) => items.iter().for_each(|item| variables(item, names)),
+        _ => {}
+    }
+}
+
+/// The variables occurring free in `ast`, following the scoping of `replace_free`.
+fn free_variables(ast: &Expr) -> HashSet<String> {
+    let in_scope = |binder: &Expr, scope: &[Expr]| {
+        let bound = bound_names(binder);
+
+        scope
+            .iter()
+            .flat_map(free_variables)
+            .filter(|name| !bound.contains(name))
+            .collect::<HashSet<String>>()
+    };
+
+    match ast {
+        Expr::Var { name, .. } => HashSet::from([name.clone()]),
+        Expr::Expr {
+            operator, operands, ..
+        } => match operator.as_ref() {
+            Expr::Keyword(Keyword::Quote) => HashSet::new(),
+            Expr::Keyword(Keyword::Quasiquote) => {
+                let mut names = HashSet::new();
+                for datum in operands {
+                    map_unquoted(datum, 0, &mut |code| {
+                        names.extend(free_variables(code));
+                        code.clone()
+                    });
+                }
+
+                names
+            }
+            Expr::Keyword(Keyword::Lambda | Keyword::Def) if !operands.is_empty() => {
+                in_scope(&operands[0], &operands[1..])
+            }
+            // The names of `let` scope over the body, those of `letrec` over the values too,
+            // and each name of `let*` over the bindings following it.
+            Expr::Keyword(kw @ (Keyword::Let | Keyword::LetStar | Keyword::Letrec)) => {
+                let bindings = match operands.first().and_then(let_bindings) {
+                    Some(bindings) => bindings,
+                    None => return operands.iter().flat_map(free_variables).collect(),
+                };
+                let values = bindings.iter().map(|(_, value)| value.clone());
+                let body = &operands[1..];
+
+                match (kw, bindings.split_first()) {
+                    (Keyword::Let, _) => {
+                        let mut names = in_scope(&let_binder(&bindings), body);
+                        names.extend(values.flat_map(|value| free_variables(&value)));
+
+                        names
+                    }
+                    (Keyword::Letrec, _) => in_scope(
+                        &let_binder(&bindings),
+                        &values.chain(body.iter().cloned()).collect::<Vec<Expr>>(),
+                    ),
+                    (_, Some(((name, value), rest))) => {
+                        let rest = Expr::Expr {
+                            operator: Box::new(Expr::Keyword(*kw)),
+                            operands: let_operands(rest.to_vec(), body.to_vec()),
+                            span: None,
+                        };
+
+                        let mut names =
+                            in_scope(&let_binder(&[(name.clone(), value.clone())]), &[rest]);
+                        names.extend(free_variables(value));
+
+                        names
+                    }
+                    (_, None) => body.iter().flat_map(free_variables).collect(),
+                }
+            }
+            Expr::Keyword(Keyword::External) => {
+                operands.iter().skip(1).flat_map(free_variables).collect()
+            }
+            Expr::Keyword(Keyword::Match) => operands
+                .iter()
+                .enumerate()
+                .flat_map(|(i, operand)| match clause_parts(operand) {
+                    Some((pattern, guard, body)) if i > 0 => in_scope(
+                        &pattern,
+                        &guard.into_iter().chain([body]).collect::<Vec<Expr>>(),
+                    ),
+                    _ => free_variables(operand),
+                })
+                .collect(),
+            Expr::Keyword(_) => operands.iter().flat_map(free_variables).collect(),
+            operator => std::iter::once(operator)
+                .chain(operands)
+                .flat_map(free_variables)
+                .collect(),
+        },
+        Expr::List(items) => items.iter().flat_map(free_variables).collect(),
+        _ => HashSet::new(),
+    }
+}
+
+/// Rebuilds a quasiquoted datum, passing the code unquoted at `depth` 0 through `f`.
+fn map_unquoted(datum: &Expr, depth: usize, f: &mut dyn FnMut(&Expr) -> Expr) -> Expr {
+    let items = match datum {
+        Expr::List(items) => items,
+        Expr::Vector(items) => {
+            return Expr::Vector(
+                items
+                    .iter()
+                    .map(|item| map_unquoted(item, depth, f))
+                    .collect(),
+            )
+        }
+        Expr::Map(map) => {
+            return Expr::Map(map.iter().fold(Map::new(), |result, (key, value)| {
+                result.insert(key.clone(), map_unquoted(value, depth, f))
+            }))
+        }
+        datum => return datum.clone(),
+    };
+
+    if let Some((keyword, operand)) = prefixed(datum) {
+        let operand = match keyword {
+            Keyword::Unquote | Keyword::UnquoteSplicing if depth == 0 => {
+                Some(f(&operand.to_code()).to_datum())
+            }
+            Keyword::Unquote | Keyword::UnquoteSplicing => {
+                Some(map_unquoted(operand, depth - 1, f))
+            }
+            Keyword::Quasiquote => Some(map_unquoted(operand, depth + 1, f)),
+            _ => None,
+        };
+
+        if let Some(operand) = operand {
+            return Expr::List([Expr::Keyword(keyword), operand].into_iter().collect());
+        }
+    }
+
+    Expr::List(
+        items
+            .iter()
+            .map(|item| map_unquoted(item, depth, f))
+            .collect(),
+    )
+}
+
+/// Renames the variable `from` to `to` in a binder.
+fn rename(binder: &Expr, from: &str, to: &str) -> Expr {
+    match binder {
+        Expr::Var { name, span } if name == from => Expr::Var {
+            name: to.to_string(),
+            span: *span,
+        },
+        Expr::Expr {
+            operator,
+            operands,
+            span,
+        } => Expr::Expr {
+            operator: Box::new(rename(operator, from, to)),
+            operands: operands
+                .iter()
+                .map(|operand| rename(operand, from, to))
+                .collect(),
+            span: *span,
+        },
+        Expr::List(items) => Expr::List(items.iter().map(|item| rename(item, from, to)).collect()),
+        binder => binder.clone(),
+    }
+}
+
+/// Splits a datum like `(unquote x)` into its keyword and operand.
+fn prefixed(datum: &Expr) -> Option<(Keyword, &Expr)> {
+    let items = match datum {
+        Expr::List(items) => items,
+        _ => return None,
+    };
+
+    match (
+        items.head(),
+        items.tail().and_then(List::head),
+        items.tail().and_then(List::tail),
+    ) {
+        (Some(Expr::Keyword(keyword)), Some(operand), Some(rest))
+            if keyword.prefix().is_some() && rest.is_empty() =>
+        {
+            Some((*keyword, operand))
+        }
+        _ => None,
+    }
+}
+
+/// Splits a `match` clause into its pattern, guard and body.
+fn clause_parts(clause: &Expr) -> Option<(Expr, Option<Expr>, Expr)> {
+    let parts = match clause {
+        Expr::List(parts) => parts.iter().cloned().collect::<Vec<Expr>>(),
+        _ => return None,
+    };
+
+    match &parts[..] {
+        [pattern, body] => Some((pattern.clone(), None, body.clone())),
+        [pattern, Expr::Var { name, .. }, guard, body] if name == GUARD => {
+            Some((pattern.clone(), Some(guard.clone()), body.clone()))
+        }
+        _ => None,
+    }
+}
+
+/// How many evaluations may be nested, such as the calls of a recursion that is not in
+/// tail position. Deeper ones fail rather than overflow the stack.
+pub const MAX_DEPTH: usize = 10_000;
+
+/// What remains to be done after a step of evaluation.
+#[derive(Debug)]
+enum Step {
+    Value(Expr),
+    /// Evaluate the expression in the frame, in place of the current one.
+    Eval(Expr, Env),
+    /// Evaluate the body of the closure in the frame binding its argument.
+    Call(Rc<Closure>, Env),
+}
+
 #[derive(Debug)]
 pub struct Runtime {
-    stack: VecDeque<Frame>,
+    global: Env,
+    stack: VecDeque<Env>,
+    externals: HashMap<String, External>,
+    macros: HashMap<String, Macro>,
+    /// The number of names made by `gensym` so far.
+    gensyms: usize,
+    cycles: Cycles,
+    /// The number of evaluations in progress, each nested in the previous one.
+    depth: usize,
 }
 
 impl Runtime {
     pub fn new() -> Self {
-        Self {
+        let mut runtime = Self {
+            global: Frame::new(None),
             stack: VecDeque::new(),
-        }
+            externals: HashMap::new(),
+            macros: HashMap::new(),
+            gensyms: 0,
+            cycles: Cycles::default(),
+            depth: 0,
+        };
+
+        external::prelude(&mut runtime);
+
+        runtime
+    }
+
+    pub fn register_external<Args, F: IntoExternal<Args>>(&mut self, name: &str, function: F) {
+        self.externals
+            .insert(name.to_string(), External::new(name, function));
     }
 
-    fn lookup(&mut self, name: &String) -> Option<&Expr> {
+    /// The innermost frame, which expressions are currently evaluated in.
+    fn env(&self) -> Env {
         self.stack
-            .iter()
-            .fold(None, |u, frame| frame.lookup(name).or(u))
+            .back()
+            .cloned()
+            .unwrap_or_else(|| self.global.clone())
     }
 
-    fn push_var(&mut self, name: String, value: Expr) {
-        if let Some(frame) = self.stack.back_mut() {
-            frame.push(name, value);
-        } else {
-            self.stack.push_back(Frame::new(name, value));
-        }
+    fn lookup(&self, name: &str) -> Option<Option<Expr>> {
+        self.env().borrow().lookup(name)
+    }
+
+    fn with_frame<T>(&mut self, frame: Env, f: impl FnOnce(&mut Self) -> T) -> T {
+        self.stack.push_back(frame);
+        let result = f(self);
+        self.stack.pop_back();
+
+        result
     }
 
-    pub fn replace_free(&self, name: &String, value: &Expr, ast: Expr) -> Expr {
-        let new_expr = match &ast {
-            Expr::Expr { operator, operands } => match *operator.clone() {
-                Expr::Var { name: v_name } if v_name == *name => Expr::Expr {
-                    operator: Box::new(value.clone()),
+    /// Substitutes `value` for the free occurrences of `name` in `ast`. Binders that would
+    /// capture a free variable of `value` are renamed first.
+    pub fn replace_free(&self, name: &str, value: &Expr, ast: Expr) -> Expr {
+        let replace = |operand: &Expr| self.replace_free(name, value, operand.clone());
+
+        match ast {
+            Expr::Var {
+                name: ref v_name, ..
+            } if v_name == name => value.clone(),
+            Expr::Expr {
+                operator,
+                operands,
+                span,
+            } => match operator.as_ref() {
+                Expr::Keyword(Keyword::Quote) => Expr::Expr {
+                    operator,
+                    operands,
+                    span,
+                },
+                Expr::Keyword(Keyword::Quasiquote) => Expr::Expr {
+                    operator,
+                    span,
                     operands: operands
                         .iter()
-                        .map(|operand| self.replace_free(name, value, operand.clone()))
-                        .collect::<Vec<Expr>>(),
+                        .map(|datum| map_unquoted(datum, 0, &mut |code| replace(code)))
+                        .collect(),
                 },
-                Expr::Keyword(kw) => match &kw {
-                    Keyword::Def | Keyword::Lambda | Keyword::External => match operands.get(0) {
-                        Some(Expr::Var { name: v_name }) if v_name == name => ast,
-                        _ => Expr::Expr {
-                            operator: Box::new(Expr::Keyword(kw)),
-                            operands: operands
-                                .iter()
-                                .map(|operand| self.replace_free(name, value, operand.clone()))
-                                .collect::<Vec<Expr>>(),
-                        },
-                    },
-                    _ => ast,
+                // The binder of λ scopes over its body, that of δ over its value and body.
+                Expr::Keyword(Keyword::Lambda | Keyword::Def) if !operands.is_empty() => {
+                    let (binder, scope) =
+                        self.replace_in_scope(name, value, &operands[0], &operands[1..]);
+
+                    Expr::Expr {
+                        operator,
+                        span,
+                        operands: [binder].into_iter().chain(scope).collect(),
+                    }
+                }
+                Expr::Keyword(kw @ (Keyword::Let | Keyword::LetStar | Keyword::Letrec)) => {
+                    let operands = match self.replace_in_let(name, value, *kw, &operands) {
+                        Some(operands) => operands,
+                        None => operands.iter().map(replace).collect(),
+                    };
+
+                    Expr::Expr {
+                        operator,
+                        operands,
+                        span,
+                    }
+                }
+                // The name of an external is not a variable.
+                Expr::Keyword(Keyword::External) => Expr::Expr {
+                    operator,
+                    span,
+                    operands: operands
+                        .iter()
+                        .enumerate()
+                        .map(|(i, operand)| {
+                            if i == 0 {
+                                operand.clone()
+                            } else {
+                                replace(operand)
+                            }
+                        })
+                        .collect(),
                 },
-                op => Expr::Expr {
-                    operator: Box::new(op),
+                Expr::Keyword(Keyword::Match) => Expr::Expr {
+                    operator,
+                    span,
                     operands: operands
                         .iter()
-                        .map(|operand| self.replace_free(name, value, operand.clone()))
-                        .collect::<Vec<Expr>>(),
+                        .enumerate()
+                        .map(|(i, operand)| match clause_parts(operand) {
+                            Some((pattern, guard, body)) if i > 0 => {
+                                let scope = guard.into_iter().chain([body]).collect::<Vec<Expr>>();
+                                let (pattern, mut scope) =
+                                    self.replace_in_scope(name, value, &pattern, &scope);
+                                let body = scope.pop();
+                                let guard = scope.pop().map(|guard| {
+                                    [
+                                        Expr::Var {
+                                            name: GUARD.to_string(),
+                                            span: None,
+                                        },
+                                        guard,
+                                    ]
+                                });
+
+                                Expr::List(
+                                    [pattern]
+                                        .into_iter()
+                                        .chain(guard.into_iter().flatten())
+                                        .chain(body)
+                                        .collect(),
+                                )
+                            }
+                            _ => replace(operand),
+                        })
+                        .collect(),
+                },
+                Expr::Keyword(_) => Expr::Expr {
+                    operator,
+                    span,
+                    operands: operands.iter().map(replace).collect(),
+                },
+                operator => Expr::Expr {
+                    operator: Box::new(replace(operator)),
+                    span,
+                    operands: operands.iter().map(replace).collect(),
                 },
             },
-            Expr::Var { name: v_name } if v_name == name => value.clone(),
-            _ => ast,
+            Expr::List(items) => Expr::List(items.iter().map(replace).collect()),
+            ast => ast,
+        }
+    }
+
+    /// Substitutes `value` for `name` in the operands of `let`, `let*` or `letrec`, following
+    /// the scope of each form. `let*` is handled as nested `let*`s of one binding each.
+    fn replace_in_let(
+        &self,
+        name: &str,
+        value: &Expr,
+        keyword: Keyword,
+        operands: &[Expr],
+    ) -> Option<Vec<Expr>> {
+        let bindings = let_bindings(operands.first()?)?;
+        let body = &operands[1..];
+        let replace = |expr: &Expr| self.replace_free(name, value, expr.clone());
+
+        let (binder, values, body) = match (keyword, bindings.split_first()) {
+            (Keyword::Let, _) => {
+                let values = bindings
+                    .iter()
+                    .map(|(_, value)| replace(value))
+                    .collect::<Vec<Expr>>();
+                let (binder, body) =
+                    self.replace_in_scope(name, value, &let_binder(&bindings), body);
+
+                (binder, values, body)
+            }
+            (Keyword::Letrec, _) => {
+                let scope = bindings
+                    .iter()
+                    .map(|(_, value)| value.clone())
+                    .chain(body.iter().cloned())
+                    .collect::<Vec<Expr>>();
+                let (binder, mut scope) =
+                    self.replace_in_scope(name, value, &let_binder(&bindings), &scope);
+                let body = scope.split_off(bindings.len());
+
+                (binder, scope, body)
+            }
+            (_, Some(((first, first_value), rest))) => {
+                let rest = Expr::Expr {
+                    operator: Box::new(Expr::Keyword(keyword)),
+                    operands: let_operands(rest.to_vec(), body.to_vec()),
+                    span: None,
+                };
+                let (first, rest) = self.replace_in_scope(
+                    name,
+                    value,
+                    &let_binder(&[(first.clone(), first_value.clone())]),
+                    &[rest],
+                );
+                let (rest_bindings, body) = match &rest[..] {
+                    [Expr::Expr { operands, .. }] => {
+                        (let_bindings(&operands[0])?, operands[1..].to_vec())
+                    }
+                    _ => return None,
+                };
+
+                let binder = Expr::List(
+                    bound_names(&first)
+                        .into_iter()
+                        .map(|name| Expr::Var { name, span: None })
+                        .chain(rest_bindings.iter().map(|(name, _)| Expr::Var {
+                            name: name.clone(),
+                            span: None,
+                        }))
+                        .collect(),
+                );
+                let values = std::iter::once(replace(first_value))
+                    .chain(rest_bindings.into_iter().map(|(_, value)| value))
+                    .collect();
+
+                (binder, values, body)
+            }
+            (_, None) => (let_binder(&[]), vec![], body.iter().map(replace).collect()),
         };
 
-        new_expr
+        Some(let_operands(
+            bound_names(&binder).into_iter().zip(values).collect(),
+            body,
+        ))
     }
 
-    fn eval_var(&mut self, ast: &Expr) -> Result<Expr, String> {
-        match ast {
-            Expr::Var { name } => {
-                let value = self.lookup(&name);
+    /// Substitutes `value` for `name` in the scope of a binder, unless the binder shadows
+    /// it. The variables of the binder free in `value` are given fresh names beforehand.
+    fn replace_in_scope(
+        &self,
+        name: &str,
+        value: &Expr,
+        binder: &Expr,
+        scope: &[Expr],
+    ) -> (Expr, Vec<Expr>) {
+        if binds(binder, name) || !scope.iter().any(|expr| free_variables(expr).contains(name)) {
+            return (binder.clone(), scope.to_vec());
+        }
 
-                match value {
-                    Some(expr) => {
-                        let expr = expr.clone();
-                        self.eval(&expr)
-                    }
-                    None => Err(format!("Variable '{}' is not defined", name)),
-                }
+        let captured = free_variables(value);
+        let mut binder = binder.clone();
+        let mut scope = scope.to_vec();
+
+        for bound in bound_names(&binder) {
+            if !captured.contains(&bound) {
+                continue;
+            }
+
+            let mut taken = captured.clone();
+            taken.insert(name.to_string());
+            taken.extend(bound_names(&binder));
+            scope.iter().for_each(|expr| variables(expr, &mut taken));
+
+            let mut fresh = format!("{}'", bound);
+            while taken.contains(&fresh) {
+                fresh.push('\'');
             }
-            ast => Err(format!("eval_var cannot evaluate '{:?}'", ast)),
+
+            let variable = Expr::Var {
+                name: fresh.clone(),
+                span: None,
+            };
+            binder = rename(&binder, &bound, &fresh);
+            scope = scope
+                .into_iter()
+                .map(|expr| self.replace_free(&bound, &variable, expr))
+                .collect();
         }
+
+        let scope = scope
+            .into_iter()
+            .map(|expr| self.replace_free(name, value, expr))
+            .collect();
+
+        (binder, scope)
     }
 
-    fn eval_literal(&mut self, ast: &Expr) -> Result<Expr, String> {
-        match ast {
-            Expr::Literal(lit) => Ok(Expr::Literal(lit.clone())),
-            ast => Err(format!("eval_literal cannot evaluate '{:?}'", ast)),
+    /// A fresh name for a variable a macro binds, other than the names in `taken`. Programs
+    /// can write names such as `t#1` too, so those of the code passed to the macro are taken.
+    fn gensym(&mut self, name: &str, taken: &HashSet<String>) -> String {
+        loop {
+            self.gensyms += 1;
+
+            let symbol = format!("{}#{}", name, self.gensyms);
+            if !taken.contains(&symbol) {
+                return symbol;
+            }
         }
     }
 
-    fn eval_expr_keyword(&mut self, ast: &Expr) -> Result<Expr, String> {
+    /// The macro `ast` calls, if any.
+    fn macro_call(&self, ast: &Expr, bound: &HashSet<String>) -> Option<Macro> {
         match ast {
-            Expr::Expr { operator, operands } => match *operator.clone() {
-                Expr::Keyword(kw) => match kw {
-                    Keyword::Def => match operands.len() {
-                        3 => match operands.get(0).unwrap() {
-                            Expr::Var { name } => {
-                                let value = self.eval(operands.get(1).unwrap())?;
+            Expr::Expr { operator, .. } => match operator.as_ref() {
+                Expr::Var { name, .. } if !bound.contains(name) => self.macros.get(name).cloned(),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
 
-                                self.stack.push_back(Frame::new(name.clone(), value.clone()));
-                                let result =
-                                    self.eval(
-                                        &self.replace_free(
-                                            name,
-                                            &value,
-                                            operands.get(2).unwrap().clone()
-                                        )
-                                    );
+    /// Expands the macro calls in `ast`, except those whose name is `bound` by an enclosing
+    /// λ, δ, `let` or `match`, which call the local variable instead.
+    fn expand(&mut self, ast: &Expr, bound: &HashSet<String>) -> Result<Expr, EvalError> {
+        if self.macro_call(ast, bound).is_some() {
+            let expansion = self.expand_once(ast, bound)?;
 
-                                self.stack.pop_back();
+            return self.expand(&expansion, bound);
+        }
 
-                                result
-                            }
-                            e => Err(format!("invalid variable name '{:?}'", e)),
-                        },
-                        l if l > 3 => match operands.get(0).unwrap() {
-                            Expr::Var { name } => {
-                                let value = self.eval(operands.get(1).unwrap())?;
+        let (operator, operands, span) = match ast {
+            Expr::Expr {
+                operator,
+                operands,
+                span,
+            } => (operator, operands, *span),
+            Expr::List(items) => {
+                return Ok(Expr::List(
+                    items
+                        .iter()
+                        .map(|item| self.expand(item, bound))
+                        .collect::<Result<_, _>>()?,
+                ))
+            }
+            ast => return Ok(ast.clone()),
+        };
 
-                                self.push_var(name.to_owned(), value.clone());
+        let within = |binder: &Expr| {
+            let mut names = bound.clone();
+            names.extend(bound_names(binder));
 
-                                let new_operator = self.replace_free(name, &value, operands.get(2).unwrap().clone());
-                                let new_operands = operands[3..].to_vec().into_iter().map(|op| self.replace_free(name, &value, op)).collect::<Vec<Expr>>();
+            names
+        };
 
-                                self.eval(&Expr::Expr { operator: Box::new(new_operator), operands: new_operands })
+        let operands = match operator.as_ref() {
+            Expr::Keyword(Keyword::Quote) => return Ok(ast.clone()),
+            Expr::Keyword(Keyword::Quasiquote) => {
+                let mut error = None;
+                let operands = operands
+                    .iter()
+                    .map(|datum| {
+                        map_unquoted(datum, 0, &mut |code| match self.expand(code, bound) {
+                            Ok(code) => code,
+                            Err(err) => {
+                                error.get_or_insert(err);
+                                code.clone()
                             }
-                            e => Err(format!("invalid variable name '{:?}'", e)),
-                        },
-                        l => Err(format!(
-                            "def (δ) expected 3 arguments. {} arguments were provided. Example usage: (δ x 5 (ι x))",
-                            l
-                        )),
-                    },
-                    Keyword::Lambda => match operands.len() {
-                      2 => Ok(Expr::Expr { operator: Box::new(Expr::Keyword(Keyword::Lambda)), operands: operands.clone() }),
-                      3 => {
-                        match operands.get(0).unwrap() {
-                          Expr::Var { name } => {
-                            let value = self.eval(operands.get(2).unwrap()).unwrap();
-                            self.stack.push_back(Frame::new(name.to_owned(), value.clone()));
-
-                            let result = self.eval(&self.replace_free(name, &value, operands.get(1).unwrap().clone()));
-
-                            self.stack.pop_back();
-
-                            result
-                          },
-                          Expr::Literal(expected) => match self.eval(operands.get(2).unwrap()).unwrap() {
-                            Expr::Literal(actual) if actual == *expected => self.eval(operands.get(1).unwrap()),
-                            actual => Err(format!("lambda (λ) expected '{:?}' but received '{:?}'", expected, actual))
-                          },
-                          Expr::Keyword(Keyword::Ignore) => self.eval(operands.get(1).unwrap()),
-                          id => Err(format!("Invalid lambda (λ) argument: {:?}", id))
-                      }
-                      },
-                      4.. => {
-                        match operands.get(0).unwrap() {
-                          Expr::Var { name } => {
-                            let value = self.eval(operands.get(2).unwrap()).unwrap();
-                            self.stack.push_back(Frame::new(name.to_owned(), value));
-
-                            let new_operator = self.eval(operands.get(1).unwrap()).unwrap();
-
-                            let result = self.eval(&Expr::Expr { operator: Box::new(new_operator), operands: operands.get(3..).unwrap()[..].to_vec() });
-
-                            self.stack.pop_back();
-
-                            result
-                          },
-                          Expr::Literal(expected) => {match self.eval(operands.get(2).unwrap()).unwrap() {
-                            Expr::Literal(actual) if actual == *expected => {
-                              let new_op = self.eval(operands.get(1).unwrap()).unwrap();
-
-                              self.eval(&Expr::Expr { operator: Box::new(new_op), operands: operands[3..].to_vec() })
-                            },
-                            actual => Err(format!("lambda (λ) expected '{}' but received '{}'", expected, actual))
-                          }},
-                          Expr::Keyword(Keyword::Ignore) => {
-                            let new_op = self.eval(operands.get(1).unwrap()).unwrap();
-                            self.eval(&Expr::Expr { operator: Box::new(new_op), operands: operands[3..].to_vec() })
-                          },
-                          id => Err(format!("Invalid lambda (λ) argument: {:?}", id))
-                      }
-                      },
-                      l => Err(format!("lambda (λ) expected 2 or more arguments but {} arguments were provided.", l))
-                    },
-                    Keyword::Arrow => unimplemented!(),
-                    Keyword::External => unimplemented!(),
-                    Keyword::Id =>
-                      match operands.len() {
-                        0 => Ok(Expr::Expr { operator: Box::new(Expr::Keyword(Keyword::Id)), operands: vec![] }),
-                        1 => {
-                          let expr = operands.get(0).unwrap();
-                          self.eval(expr)
-                        },
-                        _ => {
-                          let new_operator = {
-                            let operator_expr = operands.get(0).unwrap();
+                        })
+                    })
+                    .collect();
+
+                match error {
+                    Some(err) => return Err(err),
+                    None => operands,
+                }
+            }
+            // The macro is defined when the form is evaluated, so only the forms after it
+            // can use it.
+            Expr::Keyword(Keyword::Defmacro) => return Ok(ast.clone()),
+            // The binder itself is not expanded.
+            Expr::Keyword(Keyword::Lambda | Keyword::Def) if !operands.is_empty() => {
+                let names = within(&operands[0]);
 
-                            self.eval(operator_expr).unwrap()
-                          };
+                std::iter::once(Ok(operands[0].clone()))
+                    .chain(
+                        operands[1..]
+                            .iter()
+                            .map(|operand| self.expand(operand, &names)),
+                    )
+                    .collect::<Result<_, _>>()?
+            }
+            Expr::Keyword(kw @ (Keyword::Let | Keyword::LetStar | Keyword::Letrec)) => {
+                match operands.first().and_then(let_bindings) {
+                    Some(bindings) => {
+                        let all = within(&let_binder(&bindings));
+                        let mut names = if *kw == Keyword::Letrec {
+                            all.clone()
+                        } else {
+                            bound.clone()
+                        };
+                        let mut expanded = vec![];
 
-                          let new_operands = operands.get(1..).unwrap()[..].to_vec();
+                        for (name, value) in bindings {
+                            expanded.push((name.clone(), self.expand(&value, &names)?));
 
-                          self.eval(&Expr::Expr { operator: Box::new(new_operator), operands: new_operands })
+                            if *kw == Keyword::LetStar {
+                                names.insert(name);
+                            }
                         }
-                      },
-                    Keyword::Ignore => match operands.len() {
-                      0 => Ok(Expr::Keyword(Keyword::Ignore)),
-                      1 => Ok(Expr::Keyword(Keyword::Nil)),
-                      2 => Ok(operands.get(2).unwrap().clone()),
-                      _ => {
-                        let new_operator = {
-                          let operator_expr = operands.get(1).unwrap();
-                          self.eval(operator_expr).unwrap()
-                        };
 
-                        let new_operands = operands.get(2..).unwrap()[..].to_vec();
+                        let body = operands[1..]
+                            .iter()
+                            .map(|operand| self.expand(operand, &all))
+                            .collect::<Result<_, _>>()?;
 
-                        self.eval(&Expr::Expr { operator: Box::new(new_operator), operands: new_operands })
-                      }
-                    },
-                    Keyword::Nil => Ok(Expr::Keyword(Keyword::Nil)),
-                },
-                op => Err(format!(
-                    "eval_expr_keyword cannot evaluate an operator '{}', {}:{}",
-                    op,
-                    file!(),
-                    line!()
-                )),
+                        let_operands(expanded, body)
+                    }
+                    // Invalid bindings are left for evaluation to report.
+                    None => operands
+                        .iter()
+                        .map(|operand| self.expand(operand, bound))
+                        .collect::<Result<_, _>>()?,
+                }
+            }
+            Expr::Keyword(Keyword::Match) => operands
+                .iter()
+                .enumerate()
+                .map(|(i, operand)| match clause_parts(operand) {
+                    Some((pattern, guard, body)) if i > 0 => {
+                        let names = within(&pattern);
+                        let guard = guard
+                            .map(|guard| {
+                                Ok::<_, EvalError>([
+                                    Expr::Var {
+                                        name: GUARD.to_string(),
+                                        span: None,
+                                    },
+                                    self.expand(&guard, &names)?,
+                                ])
+                            })
+                            .transpose()?;
+
+                        Ok(Expr::List(
+                            std::iter::once(pattern)
+                                .chain(guard.into_iter().flatten())
+                                .chain([self.expand(&body, &names)?])
+                                .collect(),
+                        ))
+                    }
+                    _ => self.expand(operand, bound),
+                })
+                .collect::<Result<_, _>>()?,
+            _ => operands
+                .iter()
+                .map(|operand| self.expand(operand, bound))
+                .collect::<Result<_, _>>()?,
+        };
+
+        Ok(Expr::Expr {
+            operator: Box::new(self.expand(operator, bound)?),
+            operands,
+            span,
+        })
+    }
+
+    /// Expands a macro call one level, leaving the macro calls in the expansion as they are.
+    fn expand_once(&mut self, ast: &Expr, bound: &HashSet<String>) -> Result<Expr, EvalError> {
+        let (definition, operands, span) = match (self.macro_call(ast, bound), ast) {
+            (Some(definition), Expr::Expr { operands, span, .. }) => (definition, operands, *span),
+            _ => return Ok(ast.clone()),
+        };
+
+        let arity = definition.parameters.len();
+        if operands.len() < arity {
+            return Err(EvalError::arity(
+                format!("macro '{}'", definition.name),
+                format!(
+                    "{}{}",
+                    arity,
+                    if definition.rest.is_some() {
+                        " or more"
+                    } else {
+                        ""
+                    }
+                ),
+                operands.len(),
+            )
+            .at(span));
+        }
+
+        // The template is evaluated in a frame of its own, where the parameters stand for
+        // the forms passed in.
+        let mut taken = HashSet::new();
+        std::iter::once(&definition.template)
+            .chain(operands)
+            .for_each(|expr| variables(expr, &mut taken));
+        let template = self.hygienic(&definition.template, &definition.names(), &taken);
+        let template = self.expand(&template, &definition.names().into_iter().collect())?;
+        let frame = Frame::new(Some(self.global.clone()));
+        for (parameter, operand) in definition.parameters.iter().zip(operands) {
+            frame
+                .borrow_mut()
+                .push(parameter.clone(), operand.to_datum().quoted());
+        }
+
+        let rest = match &definition.rest {
+            Some(rest) => {
+                let forms = operands[arity..]
+                    .iter()
+                    .map(|operand| operand.to_datum().quoted())
+                    .collect();
+                frame.borrow_mut().push(rest.clone(), Expr::List(forms));
+
+                &[][..]
+            }
+            None => &operands[arity..],
+        };
+
+        let expansion = self
+            .with_frame(frame, |runtime| runtime.evaluate(&template))
+            .map_err(|err| err.at(span))?
+            .to_code();
+
+        // The expansion has no spans of its own, so errors in it point at the macro call.
+        let expansion = match expansion {
+            Expr::Expr {
+                operator,
+                operands,
+                span: None,
+            } => Expr::Expr {
+                operator,
+                operands,
+                span,
             },
-            ast => Err(format!("eval_expr_keyword cannot evaluate '{}'", ast)),
+            Expr::Var { name, span: None } => Expr::Var { name, span },
+            expansion => expansion,
+        };
+
+        // `((m a) b)` is flattened into `(m a b)`, so the remaining operands are
+        // applied to the expansion.
+        match rest {
+            [] => Ok(expansion),
+            rest => Ok(Expr::Expr {
+                operator: Box::new(expansion),
+                operands: rest.to_vec(),
+                span,
+            }),
         }
     }
 
-    fn eval_expr_nested(&mut self, ast: &Expr) -> Result<Expr, String> {
-        match ast {
-            Expr::Expr { operator, operands } => match *operator.clone() {
-                Expr::Expr {
-                    operator: inner_op,
-                    operands: inner_operands,
-                } => {
-                    let mut new_operands = vec![];
-                    new_operands.extend(inner_operands.into_iter());
-                    new_operands.extend(operands.into_iter().map(|x| x.clone()));
-
-                    let new = Expr::Expr {
-                        operator: inner_op,
-                        operands: new_operands,
-                    };
+    /// Gives the variables bound inside a macro template fresh names, so they cannot
+    /// capture the variables of the code passed to the macro. Variables named by the
+    /// parameters stand for the forms passed in and are kept. The fresh names are none of
+    /// those in `taken`.
+    fn hygienic(
+        &mut self,
+        template: &Expr,
+        parameters: &[String],
+        taken: &HashSet<String>,
+    ) -> Expr {
+        let (operator, operands, span) = match template {
+            Expr::Expr {
+                operator,
+                operands,
+                span,
+            } => (operator, operands, *span),
+            Expr::List(items) => {
+                return Expr::List(
+                    items
+                        .iter()
+                        .map(|item| self.hygienic(item, parameters, taken))
+                        .collect(),
+                )
+            }
+            template => return template.clone(),
+        };
 
-                    self.eval(&new)
+        match operator.as_ref() {
+            Expr::Keyword(Keyword::Quote) => return template.clone(),
+            // A quasiquoted template is the code the macro expands into, whose binders are
+            // renamed like those of code.
+            Expr::Keyword(Keyword::Quasiquote) => {
+                return Expr::Expr {
+                    operator: operator.clone(),
+                    operands: operands
+                        .iter()
+                        .map(|datum| {
+                            self.hygienic(&datum.to_code(), parameters, taken)
+                                .to_datum()
+                        })
+                        .collect(),
+                    span,
                 }
-                op => Err(format!(
-                    "eval_expr_nested cannot evaluate an operator '{}'",
-                    op
-                )),
-            },
-            ast => Err(format!(
-                "eval_expr_nested cannot evaluate an expression '{}'",
-                ast
-            )),
+            }
+            _ => {}
         }
-    }
 
-    fn eval_expr_var(&mut self, ast: &Expr) -> Result<Expr, String> {
-        match ast {
-            Expr::Expr { operator, operands } => match *operator.clone() {
-                Expr::Var { name } => {
-                    let new_operator = self.eval(&Expr::Var { name }).unwrap();
-
-                    match new_operator {
-                        Expr::Expr {
-                            operator: new_operator,
-                            operands: var_operands,
-                        } => {
-                            let mut new_operands = vec![];
-
-                            new_operands.extend(var_operands.into_iter());
-                            new_operands.extend(operands.clone());
-
-                            self.eval(&Expr::Expr {
-                                operator: new_operator,
-                                operands: new_operands,
-                            })
+        let operator = self.hygienic(operator, parameters, taken);
+        let mut operands = operands
+            .iter()
+            .map(|operand| self.hygienic(operand, parameters, taken))
+            .collect::<Vec<Expr>>();
+
+        let fresh = |runtime: &mut Self, binder: &Expr| {
+            bound_names(binder)
+                .into_iter()
+                .filter(|name| !parameters.contains(name))
+                .map(|name| {
+                    let symbol = runtime.gensym(&name, taken);
+                    (name, symbol)
+                })
+                .collect::<Vec<(String, String)>>()
+        };
+
+        match &operator {
+            // The binder of λ scopes over its body, that of δ over everything but the
+            // name; a δ without a body defines a global and keeps its name.
+            Expr::Keyword(kw @ (Keyword::Lambda | Keyword::Def))
+                if operands.len() >= 2 && (*kw == Keyword::Lambda || operands.len() >= 3) =>
+            {
+                for (name, symbol) in fresh(self, &operands[0]) {
+                    let variable = Expr::Var {
+                        name: symbol.clone(),
+                        span: None,
+                    };
+                    operands[0] = rename(&operands[0], &name, &symbol);
+
+                    for operand in &mut operands[1..] {
+                        *operand = self.replace_free(&name, &variable, operand.clone());
+                    }
+                }
+            }
+            // Each name of `let*` scopes over the values following it up to the next binding of
+            // the same name, which takes over the body.
+            Expr::Keyword(kw @ (Keyword::Let | Keyword::LetStar | Keyword::Letrec)) => {
+                if let Some(mut bindings) = operands.first().and_then(let_bindings) {
+                    let mut body = operands[1..].to_vec();
+
+                    for i in 0..bindings.len() {
+                        for (name, symbol) in fresh(
+                            self,
+                            &Expr::Var {
+                                name: bindings[i].0.clone(),
+                                span: None,
+                            },
+                        ) {
+                            let variable = Expr::Var {
+                                name: symbol.clone(),
+                                span: None,
+                            };
+                            let next = bindings[i + 1..]
+                                .iter()
+                                .position(|(bound, _)| *bound == name)
+                                .map(|j| i + 1 + j);
+                            let (values, in_body) = match kw {
+                                Keyword::Let => (0..0, true),
+                                Keyword::Letrec => (0..bindings.len(), true),
+                                _ => (
+                                    i + 1..next.map_or(bindings.len(), |j| j + 1),
+                                    next.is_none(),
+                                ),
+                            };
+
+                            bindings[i].0 = symbol;
+                            for (_, value) in &mut bindings[values] {
+                                *value = self.replace_free(&name, &variable, value.clone());
+                            }
+                            if in_body {
+                                for form in &mut body {
+                                    *form = self.replace_free(&name, &variable, form.clone());
+                                }
+                            }
                         }
-                        new_operator => self.eval(&Expr::Expr {
-                            operator: Box::new(new_operator),
-                            operands: operands.clone(),
-                        }),
                     }
+
+                    operands = let_operands(bindings, body);
                 }
-                op => Err(format!(
-                    "eval_expr_var cannot evaluate an operator '{:?}', {}:{}",
-                    op,
-                    file!(),
-                    line!()
-                )),
-            },
-            ast => Err(format!("eval_expr_var cannot evaluate '{:?}'", ast)),
+            }
+            Expr::Keyword(Keyword::Match) => {
+                for operand in operands.iter_mut().skip(1) {
+                    let mut parts = match operand {
+                        Expr::List(clause) => clause.iter().cloned().collect::<Vec<Expr>>(),
+                        _ => continue,
+                    };
+
+                    if let Some(pattern) = parts.first().cloned() {
+                        for (name, symbol) in fresh(self, &pattern) {
+                            let variable = Expr::Var {
+                                name: symbol.clone(),
+                                span: None,
+                            };
+                            parts[0] = rename(&parts[0], &name, &symbol);
+
+                            for part in &mut parts[1..] {
+                                *part = self.replace_free(&name, &variable, part.clone());
+                            }
+                        }
+                    }
+
+                    *operand = Expr::List(parts.into_iter().collect());
+                }
+            }
+            _ => {}
         }
-    }
 
-    pub fn eval(&mut self, ast: &Expr) -> Result<Expr, String> {
-        self.eval_var(ast)
-            .or_else(|_| self.eval_literal(ast))
-            .or_else(|_| self.eval_expr_keyword(ast))
-            .or_else(|_| self.eval_expr_nested(ast))
-            .or_else(|_| self.eval_expr_var(ast))
+        Expr::Expr {
+            operator: Box::new(operator),
+            operands,
+            span,
+        }
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use crate::{
-        evaluator::Runtime,
-        keywords::Keyword,
-        lexer::Lexer,
-        literal::Literal,
-        parse,
-        parser::{run_parser, Expr},
-    };
+    fn eval_var(&mut self, ast: &Expr) -> Result<Expr, EvalError> {
+        match ast {
+            Expr::Var { name, span } => match self.lookup(name) {
+                Some(Some(value)) => Ok(value),
+                Some(None) => Err(EvalError::failure(format!(
+                    "Variable '{}' is used before it is initialised",
+                    name
+                ))
+                .at(*span)),
+                None => match Primitive::from_name(name) {
+                    Some(primitive) => Ok(Expr::Primitive(primitive)),
+                    None => Err(EvalError::new(
+                        ErrorKind::UnboundVariable(name.clone()),
+                        *span,
+                    )),
+                },
+            },
+            ast => Err(EvalError::failure(format!(
+                "eval_var cannot evaluate '{:?}'",
+                ast
+            ))),
+        }
+    }
+
+    fn eval_literal(&mut self, ast: &Expr) -> Result<Expr, EvalError> {
+        match ast {
+            Expr::Literal(_)
+            | Expr::Primitive(_)
+            | Expr::Closure(_)
+            | Expr::List(_)
+            | Expr::Map(_)
+            | Expr::Vector(_)
+            | Expr::Cell(_)
+            | Expr::Keyword(Keyword::Id | Keyword::Ignore | Keyword::Nil) => Ok(ast.clone()),
+            ast => Err(EvalError::failure(format!(
+                "'{}' cannot be used as a value",
+                ast
+            ))),
+        }
+    }
+
+    fn eval_all(&mut self, operands: &[Expr]) -> Result<Vec<Expr>, EvalError> {
+        operands
+            .iter()
+            .map(|operand| self.evaluate(operand))
+            .collect()
+    }
+
+    fn closure(&self, parameter: &Expr, body: &Expr) -> Result<Expr, EvalError> {
+        Ok(Expr::Closure(Rc::new(Closure {
+            parameters: Parameters::new(parameter)?,
+            body: body.clone(),
+            env: self.env(),
+            name: None,
+        })))
+    }
+
+    fn eval_expr_keyword(&mut self, ast: &Expr) -> Result<Step, EvalError> {
+        match ast {
+            Expr::Expr {
+                operator,
+                operands,
+                span,
+            } => match operator.as_ref() {
+                Expr::Keyword(kw) => match kw {
+                    Keyword::Def => match operands.first() {
+                        // Without a body, the definition outlives the expression.
+                        Some(Expr::Var { name, .. }) if operands.len() == 2 => {
+                            let value = named(self.evaluate(&operands[1])?, name);
+                            self.global.borrow_mut().push(name.clone(), value.clone());
+
+                            Ok(Step::Value(value))
+                        }
+                        Some(Expr::Var { name, .. }) if operands.len() >= 3 => {
+                            // The name scopes over the value as well as the body, like that of
+                            // `letrec`, so a λ bound by δ can refer to itself. Reading the name
+                            // before the value is bound fails.
+                            let frame = Frame::new(Some(self.env()));
+                            frame.borrow_mut().declare(name.clone());
+
+                            let value = self.with_frame(frame.clone(), |runtime| {
+                                runtime.evaluate(&operands[1])
+                            })?;
+                            let value = named(value, name);
+                            frame.borrow_mut().push(name.clone(), value);
+                            self.cycles.track(&frame);
+
+                            Ok(Step::Eval(body(&operands[2..]), frame))
+                        }
+                        Some(e) if operands.len() >= 2 => {
+                            Err(EvalError::failure(format!("invalid variable name '{}'", e))
+                                .at(*span))
+                        }
+                        _ => Err(EvalError::arity(
+                            "def (δ)".to_string(),
+                            "2 or more".to_string(),
+                            operands.len(),
+                        )
+                        .at(*span)),
+                    },
+                    Keyword::Lambda => match operands.len() {
+                        l if l >= 2 => self
+                            .closure(&operands[0], &body(&operands[1..]))
+                            .map(Step::Value)
+                            .map_err(|err| err.at(*span)),
+                        l => Err(EvalError::arity(
+                            "lambda (λ)".to_string(),
+                            "2 or more".to_string(),
+                            l,
+                        )
+                        .at(*span)),
+                    },
+                    Keyword::Arrow => match operands.split_first() {
+                        Some((head, rest)) => {
+                            let head = self.evaluate(head)?;
+                            let mut rest = self.eval_all(rest)?;
+
+                            if !is_function(&head) {
+                                return self.thread(head, rest).map_err(|err| err.at(*span));
+                            }
+
+                            // `((-> f g) x)` is flattened into `(-> f g x)`, so the leading functions
+                            // form the pipeline and whatever follows them is what it is applied to.
+                            let split = rest
+                                .iter()
+                                .position(|operand| !is_function(operand))
+                                .unwrap_or(rest.len());
+                            let arguments = rest.split_off(split);
+                            let mut pipeline = vec![head];
+                            pipeline.extend(rest);
+
+                            match arguments.split_first() {
+                                Some((value, arguments)) => {
+                                    let step = self.thread(value.clone(), pipeline);
+                                    let result = self.eval_step(step)?;
+
+                                    self.apply(result, arguments.to_vec())
+                                        .map_err(|err| err.at(*span))
+                                }
+                                None => {
+                                    let argument = Expr::Var {
+                                        name: ARROW_ARGUMENT.to_string(),
+                                        span: None,
+                                    };
+                                    pipeline.insert(0, argument.clone());
+
+                                    self.closure(
+                                        &argument,
+                                        &Expr::Expr {
+                                            operator: Box::new(Expr::Keyword(Keyword::Arrow)),
+                                            operands: pipeline,
+                                            span: *span,
+                                        },
+                                    )
+                                    .map(Step::Value)
+                                }
+                            }
+                        }
+                        None => Err(EvalError::arity(
+                            "arrow (->)".to_string(),
+                            "1 or more".to_string(),
+                            0,
+                        )
+                        .at(*span)),
+                    },
+                    Keyword::External => match operands.first() {
+                        Some(Expr::Var { name, .. }) => {
+                            let arguments = self.eval_all(&operands[1..])?;
+
+                            self.call_external(name, arguments)
+                                .map_err(|err| err.at(*span))
+                        }
+                        Some(e) => Err(EvalError::failure(format!(
+                            "Invalid external (ε) name: {}",
+                            e
+                        ))
+                        .at(*span)),
+                        None => Err(EvalError::arity(
+                            "external (ε)".to_string(),
+                            "1 or more".to_string(),
+                            0,
+                        )
+                        .at(*span)),
+                    },
+                    Keyword::Id => match operands.split_first() {
+                        None => Ok(Step::Value(Expr::Keyword(Keyword::Id))),
+                        Some((function, arguments)) => {
+                            let function = self.evaluate(function)?;
+                            let arguments = self.eval_all(arguments)?;
+
+                            self.apply(function, arguments).map_err(|err| err.at(*span))
+                        }
+                    },
+                    Keyword::Ignore => match operands.len() {
+                        0 => Ok(Step::Value(Expr::Keyword(Keyword::Ignore))),
+                        1 => Ok(Step::Value(Expr::Keyword(Keyword::Nil))),
+                        _ => {
+                            let function = self.evaluate(&operands[1])?;
+                            let arguments = self.eval_all(&operands[2..])?;
+
+                            self.apply(function, arguments).map_err(|err| err.at(*span))
+                        }
+                    },
+                    Keyword::Nil => Ok(Step::Value(Expr::Keyword(Keyword::Nil))),
+                    Keyword::Quote => match &operands[..] {
+                        [datum] => Ok(Step::Value(datum.quoted())),
+                        _ => Err(EvalError::arity(
+                            "quote (')".to_string(),
+                            "1".to_string(),
+                            operands.len(),
+                        )
+                        .at(*span)),
+                    },
+                    Keyword::Quasiquote => match &operands[..] {
+                        [datum] => self.quasiquote(datum, 0).map(Step::Value),
+                        _ => Err(EvalError::arity(
+                            "quasiquote (`)".to_string(),
+                            "1".to_string(),
+                            operands.len(),
+                        )
+                        .at(*span)),
+                    },
+                    Keyword::Unquote | Keyword::UnquoteSplicing => {
+                        Err(EvalError::failure(format!(
+                            "{} ({}) can only be used inside quasiquote (`)",
+                            kw,
+                            kw.prefix().unwrap_or_default()
+                        ))
+                        .at(*span))
+                    }
+                    Keyword::Defmacro => {
+                        let definition = Macro::new(operands).map_err(|err| err.at(*span))?;
+                        self.macros.insert(definition.name.clone(), definition);
+
+                        Ok(Step::Value(Expr::Literal(Literal::Nil)))
+                    }
+                    Keyword::Macroexpand | Keyword::Macroexpand1 => match &operands[..] {
+                        [operand] => {
+                            let mut code = self.evaluate(operand)?.to_code();
+
+                            while self.macro_call(&code, &HashSet::new()).is_some() {
+                                code = self
+                                    .expand_once(&code, &HashSet::new())
+                                    .map_err(|err| err.at(*span))?;
+
+                                if *kw == Keyword::Macroexpand1 {
+                                    break;
+                                }
+                            }
+
+                            Ok(Step::Value(code.to_datum().quoted()))
+                        }
+                        _ => Err(EvalError::arity(
+                            format!("{}", kw),
+                            "1".to_string(),
+                            operands.len(),
+                        )
+                        .at(*span)),
+                    },
+                    // Updates the nearest binding of the name, which closures sharing its frame
+                    // see too, and returns the value stored.
+                    Keyword::Set => match &operands[..] {
+                        [Expr::Var {
+                            name,
+                            span: name_span,
+                        }, value] => {
+                            let value = self.evaluate(value)?;
+
+                            if !self.env().borrow_mut().set(name, value.clone()) {
+                                return Err(EvalError::new(
+                                    ErrorKind::UnboundVariable(name.clone()),
+                                    *name_span,
+                                ));
+                            }
+
+                            Ok(Step::Value(value))
+                        }
+                        [e, _] => {
+                            Err(EvalError::failure(format!("invalid variable name '{}'", e))
+                                .at(*span))
+                        }
+                        _ => Err(EvalError::arity(
+                            "set!".to_string(),
+                            "2".to_string(),
+                            operands.len(),
+                        )
+                        .at(*span)),
+                    },
+                    Keyword::Match => match operands.split_first() {
+                        Some((scrutinee, rest)) => {
+                            let value = self.evaluate(scrutinee)?;
+
+                            // Operands following the clauses are arguments of the result.
+                            let split = rest
+                                .iter()
+                                .position(|operand| !matches!(operand, Expr::List(_)))
+                                .unwrap_or(rest.len());
+                            let (clauses, arguments) = rest.split_at(split);
+                            let arguments = self.eval_all(arguments)?;
+
+                            for clause in clauses {
+                                let (pattern, guard, body) = match clause_parts(clause) {
+                                    Some(parts) => parts,
+                                    None => {
+                                        return Err(EvalError::failure(format!(
+                                            "Invalid match clause: {}",
+                                            clause
+                                        ))
+                                        .at(*span))
+                                    }
+                                };
+
+                                let mut bindings = vec![];
+                                if !pattern::matches(&pattern, &value, &mut bindings) {
+                                    continue;
+                                }
+
+                                let frame = Frame::new(Some(self.env()));
+                                for (name, value) in bindings {
+                                    frame.borrow_mut().push(name, value);
+                                }
+
+                                if let Some(guard) = guard {
+                                    if !self
+                                        .with_frame(frame.clone(), |runtime| {
+                                            runtime.evaluate(&guard)
+                                        })?
+                                        .is_truthy()
+                                    {
+                                        continue;
+                                    }
+                                }
+
+                                if arguments.is_empty() {
+                                    return Ok(Step::Eval(body, frame));
+                                }
+
+                                let result =
+                                    self.with_frame(frame, |runtime| runtime.evaluate(&body))?;
+
+                                return self.apply(result, arguments).map_err(|err| err.at(*span));
+                            }
+
+                            Err(EvalError::new(
+                                ErrorKind::NoMatch(format!("{}", value)),
+                                *span,
+                            ))
+                        }
+                        None => {
+                            Err(
+                                EvalError::arity("match".to_string(), "1 or more".to_string(), 0)
+                                    .at(*span),
+                            )
+                        }
+                    },
+                    Keyword::If => {
+                        match operands.len() {
+                            2 | 3 => {
+                                let branch = match self.evaluate(&operands[0])?.is_truthy() {
+                                    true => operands[1].clone(),
+                                    false => operands
+                                        .get(2)
+                                        .cloned()
+                                        .unwrap_or(Expr::Literal(Literal::Nil)),
+                                };
+
+                                Ok(Step::Eval(branch, self.env()))
+                            }
+                            // `((if c f g) x)` is flattened into `(if c f g x)`.
+                            l if l > 3 => {
+                                let branch = if self.evaluate(&operands[0])?.is_truthy() {
+                                    &operands[1]
+                                } else {
+                                    &operands[2]
+                                };
+                                let function = self.evaluate(branch)?;
+                                let arguments = self.eval_all(&operands[3..])?;
+
+                                self.apply(function, arguments).map_err(|err| err.at(*span))
+                            }
+                            l => Err(EvalError::arity("if".to_string(), "2 or 3".to_string(), l)
+                                .at(*span)),
+                        }
+                    }
+                    Keyword::Cond => {
+                        for clause in operands {
+                            let parts = match clause {
+                                Expr::List(parts) => parts.iter().cloned().collect::<Vec<Expr>>(),
+                                _ => {
+                                    return Err(EvalError::failure(format!(
+                                        "Invalid cond clause: {}",
+                                        clause
+                                    ))
+                                    .at(*span))
+                                }
+                            };
+
+                            let (test, body) = match &parts[..] {
+                                [test] => (test, None),
+                                [test, body] => (test, Some(body)),
+                                _ => {
+                                    return Err(EvalError::failure(format!(
+                                        "Invalid cond clause: {}",
+                                        clause
+                                    ))
+                                    .at(*span))
+                                }
+                            };
+
+                            let value = match test {
+                                Expr::Var { name, .. } if name == ELSE => {
+                                    Expr::Literal(Literal::Bool(true))
+                                }
+                                test => self.evaluate(test)?,
+                            };
+
+                            if value.is_truthy() {
+                                return match body {
+                                    Some(body) => Ok(Step::Eval(body.clone(), self.env())),
+                                    None => Ok(Step::Value(value)),
+                                };
+                            }
+                        }
+
+                        Ok(Step::Value(Expr::Literal(Literal::Nil)))
+                    }
+                    Keyword::Let | Keyword::LetStar | Keyword::Letrec => {
+                        let bindings = match operands.first().map(let_bindings) {
+                            _ if operands.len() < 2 => {
+                                return Err(EvalError::arity(
+                                    format!("{}", kw),
+                                    "2 or more".to_string(),
+                                    operands.len(),
+                                )
+                                .at(*span))
+                            }
+                            Some(Some(bindings)) => bindings,
+                            _ => {
+                                return Err(EvalError::failure(format!(
+                                    "Invalid {} bindings: {}",
+                                    kw, operands[0]
+                                ))
+                                .at(*span))
+                            }
+                        };
+
+                        // `let*` binds its names one after another, so a later one may rebind an
+                        // earlier one.
+                        if *kw != Keyword::LetStar {
+                            let mut names = HashSet::new();
+                            if let Some((name, _)) =
+                                bindings.iter().find(|(name, _)| !names.insert(name))
+                            {
+                                return Err(EvalError::failure(format!(
+                                    "Duplicate name '{}' in {} bindings",
+                                    name, kw
+                                ))
+                                .at(*span));
+                            }
+                        }
+
+                        let mut frame = Frame::new(Some(self.env()));
+
+                        // The names of `letrec` are bound before any value is evaluated, so its
+                        // values see them rather than the names they shadow.
+                        if *kw == Keyword::Letrec {
+                            for (name, _) in &bindings {
+                                frame.borrow_mut().declare(name.clone());
+                            }
+                        }
+
+                        for (name, value) in bindings {
+                            let value = match kw {
+                                // The values of `let` cannot see the names it binds.
+                                Keyword::Let => self.evaluate(&value)?,
+                                _ => self.with_frame(frame.clone(), |runtime| {
+                                    runtime.evaluate(&value)
+                                })?,
+                            };
+
+                            // A `let*` name shadowing a visible one gets a frame of its own, so
+                            // closures made by the earlier values keep seeing the shadowed one.
+                            if *kw == Keyword::LetStar
+                                && (frame.borrow().lookup(&name).is_some()
+                                    || Primitive::from_name(&name).is_some())
+                            {
+                                frame = Frame::new(Some(frame));
+                            }
+
+                            let value = named(value, &name);
+                            frame.borrow_mut().push(name, value);
+                        }
+                        self.cycles.track(&frame);
+
+                        Ok(Step::Eval(body(&operands[1..]), frame))
+                    }
+                    Keyword::Begin => match operands.split_last() {
+                        Some((last, rest)) => {
+                            for operand in rest {
+                                self.evaluate(operand)?;
+                            }
+
+                            Ok(Step::Eval(last.clone(), self.env()))
+                        }
+                        None => Ok(Step::Value(Expr::Literal(Literal::Nil))),
+                    },
+                    Keyword::And | Keyword::Or => {
+                        // Both return the first operand deciding the result, or the last one.
+                        let decisive = matches!(kw, Keyword::Or);
+
+                        match operands.split_last() {
+                            Some((last, rest)) => {
+                                for operand in rest {
+                                    let value = self.evaluate(operand)?;
+
+                                    if value.is_truthy() == decisive {
+                                        return Ok(Step::Value(value));
+                                    }
+                                }
+
+                                Ok(Step::Eval(last.clone(), self.env()))
+                            }
+                            None => Ok(Step::Value(Expr::Literal(Literal::Bool(!decisive)))),
+                        }
+                    }
+                },
+                op => Err(EvalError::failure(format!(
+                    "eval_expr_keyword cannot evaluate an operator '{}', {}:{}",
+                    op,
+                    file!(),
+                    line!()
+                ))),
+            },
+            ast => Err(EvalError::failure(format!(
+                "eval_expr_keyword cannot evaluate '{}'",
+                ast
+            ))),
+        }
+    }
+
+    fn eval_expr_application(&mut self, ast: &Expr) -> Result<Step, EvalError> {
+        match ast {
+            Expr::Expr {
+                operator,
+                operands,
+                span,
+            } => {
+                let function = self.evaluate(operator)?;
+                let arguments = self.eval_all(operands)?;
+
+                self.apply(function, arguments).map_err(|err| err.at(*span))
+            }
+            ast => Err(EvalError::failure(format!(
+                "eval_expr_application cannot evaluate '{}'",
+                ast
+            ))),
+        }
+    }
+
+    /// Binds the arguments of a closure in a new frame for its body.
+    fn bind(&self, closure: &Closure, arguments: Vec<Expr>) -> Result<Env, EvalError> {
+        let frame = Frame::new(Some(closure.env.clone()));
+
+        let (required, rest) = match &closure.parameters {
+            Parameters::Single(parameter) => (std::slice::from_ref(parameter), None),
+            Parameters::List { required, rest } => (&required[..], rest.as_ref()),
+        };
+
+        if arguments.len() < required.len() || (rest.is_none() && arguments.len() > required.len())
+        {
+            return Err(EvalError::arity(
+                closure.describe(),
+                closure.parameters.arity(),
+                arguments.len(),
+            ));
+        }
+
+        let mut arguments = arguments.into_iter();
+
+        for (parameter, argument) in required.iter().zip(arguments.by_ref()) {
+            match parameter {
+                Expr::Var { name, .. } => frame.borrow_mut().push(name.clone(), argument),
+                Expr::Literal(expected) => match &argument {
+                    Expr::Literal(actual) if actual == expected => {}
+                    actual => {
+                        return Err(EvalError::new(
+                            ErrorKind::PatternMismatch {
+                                function: closure.describe(),
+                                expected: format!("{}", expected),
+                                received: format!("{}", actual),
+                            },
+                            None,
+                        ))
+                    }
+                },
+                _ => {}
+            }
+        }
+
+        if let Some(Expr::Var { name, .. }) = rest {
+            frame
+                .borrow_mut()
+                .push(name.clone(), Expr::List(arguments.collect()));
+        }
+
+        Ok(frame)
+    }
+
+    fn call_external(&mut self, name: &str, mut arguments: Vec<Expr>) -> Result<Step, EvalError> {
+        let external = match self.externals.get(name) {
+            Some(external) => external.clone(),
+            None => {
+                return Err(EvalError::failure(format!(
+                    "external (ε) '{}' is not registered",
+                    name
+                )))
+            }
+        };
+
+        if arguments.len() < external.arity() {
+            let mut partial = vec![Expr::Var {
+                name: name.to_string(),
+                span: None,
+            }];
+            partial.extend(arguments);
+
+            return Ok(Step::Value(Expr::Expr {
+                operator: Box::new(Expr::Keyword(Keyword::External)),
+                operands: partial,
+                span: None,
+            }));
+        }
+
+        let rest = arguments.split_off(external.arity());
+        let result = external.call(&arguments)?;
+
+        if rest.is_empty() || is_function(&result) {
+            self.apply(result, rest)
+        } else {
+            Err(EvalError::arity(
+                format!("external (ε) '{}'", name),
+                format!("{}", external.arity()),
+                external.arity() + rest.len(),
+            ))
+        }
+    }
+
+    /// Applies an evaluated function to evaluated arguments, one λ parameter at a time.
+    /// Only the last closure call is left to the caller as a tail call.
+    fn apply(&mut self, function: Expr, arguments: Vec<Expr>) -> Result<Step, EvalError> {
+        // Functions are called even without arguments, which fails unless they take none,
+        // like `list` or `(λ (. xs) xs)`.
+        if arguments.is_empty() {
+            match &function {
+                Expr::Primitive(primitive) => {
+                    return self.apply_primitive(*primitive, &arguments);
+                }
+                Expr::Closure(closure) => {
+                    let frame = self.bind(closure, arguments)?;
+
+                    return Ok(Step::Call(closure.clone(), frame));
+                }
+                _ => {}
+            }
+        }
+
+        let mut function = function;
+        let mut arguments = arguments.into_iter();
+
+        while let Some(argument) = arguments.next() {
+            function = match function {
+                Expr::Closure(closure) if closure.parameters.is_empty() => {
+                    // `(λ () ...)` is called first, and what it returns takes the arguments.
+                    let frame = self.bind(&closure, vec![])?;
+                    let result = self.eval_step(Ok(Step::Call(closure.clone(), frame)))?;
+
+                    if !is_function(&result) {
+                        return Err(EvalError::arity(
+                            closure.describe(),
+                            closure.parameters.arity(),
+                            1 + arguments.len(),
+                        ));
+                    }
+
+                    return self
+                        .apply(result, std::iter::once(argument).chain(arguments).collect());
+                }
+                Expr::Closure(closure) => {
+                    // A parameter list takes as many arguments as it declares, a rest
+                    // parameter all of them.
+                    let mut all = vec![argument];
+                    match &closure.parameters {
+                        Parameters::Single(_) => {}
+                        Parameters::List { rest: Some(_), .. } => all.extend(arguments.by_ref()),
+                        Parameters::List { required, .. } => {
+                            all.extend(arguments.by_ref().take(required.len().saturating_sub(1)))
+                        }
+                    }
+
+                    let received = all.len();
+                    let frame = self.bind(&closure, all)?;
+
+                    if arguments.len() == 0 {
+                        return Ok(Step::Call(closure, frame));
+                    }
+
+                    let result = self.eval_step(Ok(Step::Call(closure.clone(), frame)))?;
+
+                    // Extra arguments are only accepted by functions returning functions.
+                    if !is_function(&result) {
+                        return Err(EvalError::arity(
+                            closure.describe(),
+                            closure.parameters.arity(),
+                            received + arguments.len(),
+                        ));
+                    }
+
+                    result
+                }
+                Expr::Keyword(Keyword::Id) => argument,
+                Expr::Primitive(primitive) => {
+                    let mut all = vec![argument];
+                    all.extend(arguments);
+
+                    return self.apply_primitive(primitive, &all);
+                }
+                Expr::Expr {
+                    operator, operands, ..
+                } if matches!(operator.as_ref(), Expr::Keyword(Keyword::External)) => {
+                    return match operands.split_first() {
+                        Some((Expr::Var { name, .. }, previous)) => {
+                            let mut all = previous.to_vec();
+                            all.push(argument);
+                            all.extend(arguments);
+
+                            self.call_external(name, all)
+                        }
+                        _ => Err(EvalError::failure("Invalid external (ε) value".to_string())),
+                    };
+                }
+                function => {
+                    return Err(EvalError::type_error(
+                        "application".to_string(),
+                        "a function",
+                        &function,
+                    ))
+                }
+            };
+        }
+
+        Ok(Step::Value(function))
+    }
+
+    fn apply_primitive(
+        &mut self,
+        primitive: Primitive,
+        arguments: &[Expr],
+    ) -> Result<Step, EvalError> {
+        primitive
+            .apply(arguments, &mut |function, arguments| {
+                let step = self.apply(function.clone(), arguments);
+
+                self.eval_step(step)
+            })
+            .map(Step::Value)
+    }
+
+    /// Builds the value of a quasiquoted datum, evaluating what is unquoted at `depth` 0.
+    /// Nested quasiquotes increase the depth and unquotes decrease it.
+    fn quasiquote(&mut self, datum: &Expr, depth: usize) -> Result<Expr, EvalError> {
+        let items = match datum {
+            Expr::List(items) => items,
+            Expr::Vector(items) => {
+                return Ok(Expr::Vector(
+                    self.quasiquote_items(items.iter(), depth)?
+                        .into_iter()
+                        .collect(),
+                ))
+            }
+            // Keys are literals, so only the values can be unquoted.
+            Expr::Map(map) => {
+                let mut result = Map::new();
+                for (key, value) in map.iter() {
+                    result = result.insert(key.clone(), self.quasiquote(value, depth)?);
+                }
+
+                return Ok(Expr::Map(result));
+            }
+            datum => return Ok(datum.quoted()),
+        };
+
+        if let Some((keyword, operand)) = prefixed(datum) {
+            let depth = match keyword {
+                Keyword::Unquote if depth == 0 => return self.evaluate(&operand.to_code()),
+                Keyword::UnquoteSplicing if depth == 0 => {
+                    return Err(EvalError::failure(
+                        "unquote-splicing (,@) can only be used inside a list or a vector"
+                            .to_string(),
+                    ))
+                }
+                Keyword::Unquote | Keyword::UnquoteSplicing => Some(depth - 1),
+                Keyword::Quasiquote => Some(depth + 1),
+                _ => None,
+            };
+
+            if let Some(depth) = depth {
+                return Ok(Expr::List(
+                    [Expr::Keyword(keyword), self.quasiquote(operand, depth)?]
+                        .into_iter()
+                        .collect(),
+                ));
+            }
+        }
+
+        Ok(Expr::List(
+            self.quasiquote_items(items.iter(), depth)?
+                .into_iter()
+                .collect(),
+        ))
+    }
+
+    /// Builds the items of a quasiquoted list or vector, splicing in those unquoted with
+    /// `,@` at `depth` 0.
+    fn quasiquote_items<'a>(
+        &mut self,
+        items: impl Iterator<Item = &'a Expr>,
+        depth: usize,
+    ) -> Result<Vec<Expr>, EvalError> {
+        let mut result = vec![];
+
+        for item in items {
+            match prefixed(item) {
+                Some((Keyword::UnquoteSplicing, operand)) if depth == 0 => {
+                    match self.evaluate(&operand.to_code())? {
+                        Expr::List(list) => result.extend(list.iter().cloned()),
+                        value => {
+                            return Err(EvalError::type_error(
+                                "unquote-splicing (,@)".to_string(),
+                                "a list",
+                                &value,
+                            ))
+                        }
+                    }
+                }
+                _ => result.push(self.quasiquote(item, depth)?),
+            }
+        }
+
+        Ok(result)
+    }
+
+    /// Passes `value` through each of `functions` in turn, the last application being a tail call.
+    fn thread(&mut self, value: Expr, functions: Vec<Expr>) -> Result<Step, EvalError> {
+        let mut step = Step::Value(value);
+
+        for function in functions {
+            let value = self.eval_step(Ok(step))?;
+            step = self.apply(function, vec![value])?;
+        }
+
+        Ok(step)
+    }
+
+    /// Takes a single step of evaluation, leaving tail positions unevaluated.
+    fn step(&mut self, ast: &Expr) -> Result<Step, EvalError> {
+        match ast {
+            Expr::Var { .. } => self.eval_var(ast).map(Step::Value),
+            Expr::Expr { operator, .. } => match operator.as_ref() {
+                Expr::Keyword(_) => self.eval_expr_keyword(ast),
+                _ => self.eval_expr_application(ast),
+            },
+            _ => self.eval_literal(ast).map(Step::Value),
+        }
+        .map_err(|err| err.at(ast.span()))
+    }
+
+    /// Runs steps until a value is produced. Each tail call replaces the frame of the
+    /// previous one, so loops written as recursion run in constant Rust and frame stack.
+    fn eval_step(&mut self, step: Result<Step, EvalError>) -> Result<Expr, EvalError> {
+        if self.depth >= MAX_DEPTH {
+            return Err(EvalError::failure(format!(
+                "Recursion too deep, over {} nested evaluations",
+                MAX_DEPTH
+            )));
+        }
+
+        self.depth += 1;
+        let depth = self.stack.len();
+        let mut step = step;
+
+        let result = loop {
+            match step {
+                Ok(Step::Value(value)) => break Ok(value),
+                Ok(Step::Eval(ast, frame)) => {
+                    self.stack.truncate(depth);
+                    self.stack.push_back(frame);
+                    step = self.step(&ast);
+                }
+                Ok(Step::Call(closure, frame)) => {
+                    self.stack.truncate(depth);
+                    self.stack.push_back(frame);
+                    step = self.step(&closure.body);
+                }
+                Err(err) => break Err(err),
+            }
+        };
+
+        self.stack.truncate(depth);
+        self.depth -= 1;
+
+        result
+    }
+
+    fn evaluate(&mut self, ast: &Expr) -> Result<Expr, EvalError> {
+        let step = self.step(ast);
+
+        self.eval_step(step)
+    }
+
+    /// Expands the macros in `ast`, then evaluates it.
+    pub fn eval(&mut self, ast: &Expr) -> Result<Expr, EvalError> {
+        let ast = self.expand(ast, &HashSet::new())?;
+
+        self.evaluate(&ast)
+    }
+
+    /// Evaluates top-level forms in order, returning the value of the last one.
+    pub fn eval_program(&mut self, program: &[Expr]) -> Result<Expr, EvalError> {
+        program
+            .iter()
+            .try_fold(Expr::Literal(Literal::Nil), |_, form| self.eval(form))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        error::ErrorKind,
+        evaluator::{Runtime, MAX_DEPTH},
+        external, frame, parse,
+        parser::{parse_program, Expr, MAX_NESTING},
+        position::{Position, Span},
+    };
+
+    macro_rules! t {
+        ($src:expr, $name:expr, $val:expr, $expected:expr) => {
+            assert_eq!(
+                format!(
+                    "{}",
+                    Runtime::new().replace_free($name, &parse!($val), parse!($src))
+                ),
+                $expected
+            );
+        };
+    }
+
+    macro_rules! e {
+        ($src:expr, $expected:expr) => {
+            assert_eq!(
+                format!("{}", Runtime::new().eval(&parse!($src)).unwrap()),
+                $expected
+            );
+        };
+    }
+
+    #[test]
+    fn replace_free_lambda() {
+        t!("(x (λ x x) x)", "x", "1", "(1 (λ x x) 1)");
+        t!(
+            "(f (λ f (f x)) 5)",
+            "f",
+            "(λ _ 5)",
+            "((λ _ 5) (λ f (f x)) 5)"
+        );
+    }
+
+    #[test]
+    fn replace_free_match_and_quote() {
+        t!("(match x (x x) (y x))", "x", "1", "(match 1 (x x) (y 1))");
+        t!("(x '(x) `(x ,x))", "x", "1", "(1 '(x) `(x ,1))");
+        t!("`(x `(,x ,,x))", "x", "1", "`(x `(,x ,,1))");
+        t!("(λ (a . x) x)", "x", "1", "(λ (a . x) x)");
+    }
+
+    #[test]
+    fn replace_free_let() {
+        t!("(let ((x y)) (x y))", "y", "1", "(let ((x 1)) (x 1))");
+        t!("(let ((y y)) y)", "y", "2", "(let ((y 2)) y)");
+        t!("(letrec ((y y)) y)", "y", "2", "(letrec ((y y)) y)");
+        t!(
+            "(let* ((a y) (y a) (b y)) y)",
+            "y",
+            "1",
+            "(let* ((a 1) (y a) (b y)) y)"
+        );
+        t!("(let ((x 1)) y)", "y", "x", "(let ((x' 1)) x)");
+        t!(
+            "(let* ((a 1) (x a)) (y x))",
+            "y",
+            "x",
+            "(let* ((a 1) (x' a)) (x x'))"
+        );
+    }
+
+    #[test]
+    fn replace_free_avoids_capture() {
+        t!("(λ x y)", "y", "x", "(λ x' x)");
+        t!("(λ x (y x'))", "y", "x", "(λ x'' (x x'))");
+        t!("((λ x y) x)", "x", "z", "((λ x y) z)");
+        t!("(δ f (f y) f)", "y", "(f 1)", "(δ f' (f' (f 1)) f')");
+        t!(
+            "(match y ((a b) (+ a y)))",
+            "y",
+            "a",
+            "(match a ((a' b) (+ a' a)))"
+        );
+        t!(
+            "(λ x (λ y (x y z)))",
+            "z",
+            "(x y)",
+            "(λ x' (λ y' (x' y' (x y))))"
+        );
+        t!("(λ x `(x ,y))", "y", "x", "(λ x' `(x ,x))");
+    }
+
+    #[test]
+    fn replace_free_def() {
+        t!("(x (δ x x) y x)", "x", "1", "(1 (δ x x) y 1)");
+        t!("(δ f (λ x y x))", "y", "ι", "(δ f (λ x ι x))");
+        t!(
+            "(δ true (λ p (λ q p))
+      (δ false (λ p (λ q q))
+        true
+      )
+    )",
+            "p",
+            "42",
+            "(δ true (λ p (λ q p)) (δ false (λ p (λ q q)) true))"
+        );
+    }
+
+    #[test]
+    fn external() {
+        e!("(ε sqrt 16)", "4");
+        e!("(ε pow 2 10)", "1024");
+        e!("(ε pow 2)", "(ε pow 2)");
+        e!("(δ p (ε pow 2) (p 3))", "8");
+        e!("(ε string-length \"abc\")", "3");
+
+        let mut runtime = Runtime::new();
+        runtime.register_external("add", |a: i64, b: i64| a + b);
+        assert_eq!(
+            format!("{}", runtime.eval(&parse!("(ε add 1 2)")).unwrap()),
+            "3"
+        );
+
+        assert!(runtime.eval(&parse!("(ε add 1 2 3)")).is_err());
+        assert!(runtime.eval(&parse!("(ε add 1 \"2\")")).is_err());
+        assert!(runtime.eval(&parse!("(ε missing 1)")).is_err());
+    }
+
+    #[test]
+    fn arrow() {
+        e!("(-> 5)", "5");
+        e!("(-> 16 (ε sqrt) (ε pow 2))", "16");
+        e!("((-> (ε sqrt) (ε sqrt)) 16)", "2");
+        e!("(δ f (-> (λ x x) (ε sqrt) (ι)) (f 9))", "3");
+        e!("(-> 0 (λ 0 \"zero\"))", "\"zero\"");
+        e!("(-> (ε sqrt))", "(λ ->x (-> ->x (ε sqrt)))");
+        e!("((-> (ε pow) (ι)) 2 3)", "8");
+
+        assert!(Runtime::new()
+            .eval(&parse!("(-> 1 (λ 0 \"zero\"))"))
+            .is_err());
+    }
+
+    #[test]
+    fn primitives() {
+        e!("(+ 1 2 3)", "6");
+        e!("(- 10 4 1)", "5");
+        e!("(- 3)", "-3");
+        e!("(* 2 (+ 1 2))", "6");
+        e!("(/ 1 4)", "1/4");
+        e!("(/ 1.0 4)", "0.25");
+        e!("(mod -7 3)", "2");
+        e!("(< 1 2 3)", "#t");
+        e!("(>= 1 2)", "#f");
+        e!("(= 2 (+ 1 1))", "#t");
+        e!("(min 3 1 2)", "1");
+        e!("(max 3 1 2)", "3");
+        e!("(abs -2.5)", "2.5");
+        e!("(floor 2.5)", "2.0");
+        e!("(floor -5/2)", "-3");
+        e!("(δ sq (λ x (* x x)) (sq 7))", "49");
+        e!("(-> -4 abs (ε sqrt))", "2");
+
+        assert!(Runtime::new().eval(&parse!("(+ 1 \"2\")")).is_err());
+        assert!(Runtime::new().eval(&parse!("(/ 1 0)")).is_err());
+        assert!(Runtime::new().eval(&parse!("(abs 1 2)")).is_err());
+    }
+
+    #[test]
+    fn numbers() {
+        e!("42", "42");
+        e!("-0xff", "-255");
+        e!("6/4", "3/2");
+        e!("4/2", "2");
+        e!("1.5e3", "1500.0");
+        e!("-.5", "-0.5");
+        e!("(+ 1/2 1/3)", "5/6");
+        e!("(* 2/3 3/2)", "1");
+        e!("(+ 1/2 0.5)", "1.0");
+        e!("(= (+ 1/10 2/10) 3/10)", "#t");
+        e!("(= 1 1.0)", "#t");
+        e!("(< 1/3 0.34 1)", "#t");
+        e!("(+ 9223372036854775807 1)", "9223372036854775808");
+        e!("(- -9223372036854775808 1)", "-9223372036854775809");
+        e!("(* 4294967296 4294967296)", "18446744073709551616");
+        e!("(- (+ 9223372036854775807 1) 1)", "9223372036854775807");
+        e!("(/ 36893488147419103232 3)", "36893488147419103232/3");
+        e!("(mod -7/2 2)", "1/2");
+        e!("(mod 7.5 -2)", "1.5");
+        e!("(abs -9223372036854775808)", "9223372036854775808");
+        e!("(min 2 1.5 3)", "1.5");
+        e!("(ε pow 2 100)", "1267650600228229401496703205376");
+        e!("(ε pow 2 -2)", "1/4");
+        e!("(ε pow 4 0.5)", "2.0");
+        e!("(ε sqrt 9/4)", "3/2");
+        e!("(ε sqrt 2)", "1.4142135623730951");
+        e!("(δ inf 1 inf)", "1");
+
+        assert!(Runtime::new().eval(&parse!("(/ 1 0.0)")).is_err());
+        assert!(Runtime::new().eval(&parse!("(mod 1/2 0)")).is_err());
+        let err = Runtime::new().eval(&parse!("1/0")).unwrap_err();
+        assert_eq!(err.kind, ErrorKind::UnboundVariable("1/0".to_string()));
+    }
 
-    macro_rules! t {
-        ($src:expr, $name:expr, $val:expr, $expected:expr) => {
+    #[test]
+    fn error_spans() {
+        let ast = parse!("(ι\n  foo)");
+        let foo = match &ast {
+            Expr::Expr { operands, .. } => operands[0].clone(),
+            _ => unreachable!(),
+        };
+        let err = Runtime::new().eval_var(&foo).unwrap_err();
+        assert_eq!(err.kind, ErrorKind::UnboundVariable("foo".to_string()));
+        assert_eq!(
+            err.span,
+            Some(Span::new(Position::new(1, 2), Position::new(1, 5)))
+        );
+        assert_eq!(format!("{}", err), "2:3: Variable 'foo' is not defined");
+
+        let err = Runtime::new()
+            .eval_expr_keyword(&parse!("(ε sqrt 1 2)"))
+            .unwrap_err();
+        assert_eq!(
+            err.kind,
+            ErrorKind::ArityMismatch {
+                function: "external (ε) 'sqrt'".to_string(),
+                expected: "1".to_string(),
+                received: 2
+            }
+        );
+        assert_eq!(
+            err.span,
+            Some(Span::new(Position::new(0, 0), Position::new(0, 12)))
+        );
+
+        let err = Runtime::new()
+            .eval_expr_application(&parse!("((λ 0 1) 2)"))
+            .unwrap_err();
+        assert_eq!(
+            err.kind,
+            ErrorKind::PatternMismatch {
+                function: "lambda (λ)".to_string(),
+                expected: "0".to_string(),
+                received: "2".to_string()
+            }
+        );
+
+        let err = Runtime::new()
+            .eval_expr_keyword(&parse!("(ε sqrt \"a\")"))
+            .unwrap_err();
+        assert!(matches!(err.kind, ErrorKind::TypeError { .. }));
+
+        let err = parse_program("(ι 1\n  a\"b)").unwrap_err();
+        assert!(matches!(err.kind, ErrorKind::Parse(_)));
+        assert_eq!(
+            err.span,
+            Some(Span::new(Position::new(1, 2), Position::new(1, 6)))
+        );
+        assert!(parse_program("(ι 1").is_err());
+    }
+
+    #[test]
+    fn closures() {
+        e!("(δ x 1 ((λ x x) 2))", "2");
+        e!("(δ x 1 (δ f (λ y x) (δ x 2 (f 0))))", "1");
+        e!("(δ add (λ a (λ b (+ a b))) (δ inc (add 1) (inc 41)))", "42");
+        e!(
+            "(δ true (λ p (λ q p))
+               (δ false (λ p (λ q q))
+                 (true 1 2)
+               )
+             )",
+            "1"
+        );
+        e!("(δ f (λ x (+ x 1)) f)", "(λ x (+ x 1))");
+        e!(
+            "(def hello (lambda _ \"hello there\") (
+               (hello nil)
+             ))",
+            "\"hello there\""
+        );
+
+        let mut runtime = Runtime::new();
+        runtime.register_external("choose", |c: bool, a: Expr, b: Expr| if c { a } else { b });
+        assert_eq!(
+            format!(
+                "{}",
+                runtime
+                    .eval(&parse!(
+                        "(δ fact (λ n ((ε choose (= n 0) (λ _ 1) (λ _ (* n (fact (- n 1))))) Ω))
+                           (fact 10))"
+                    ))
+                    .unwrap()
+            ),
+            "3628800"
+        );
+    }
+
+    #[test]
+    fn tail_calls() {
+        let mut runtime = Runtime::new();
+        runtime.register_external("choose", |c: bool, a: Expr, b: Expr| if c { a } else { b });
+
+        let mut run = |src: &str| format!("{}", runtime.eval(&parse!(src)).unwrap());
+
+        assert_eq!(
+            run(
+                "(δ loop (λ n ((ε choose (= n 0) (λ _ \"done\") (λ _ (loop (- n 1)))) Ω))
+                   (loop 1000000))"
+            ),
+            "\"done\""
+        );
+        assert_eq!(
+            run("(δ sum (λ acc (λ n ((ε choose (= n 0) (λ _ acc) (λ _ (δ m (- n 1) (sum (+ acc n) m)))) Ω)))
+                   (sum 0 100000))"),
+            "5000050000"
+        );
+        assert_eq!(
+            run(
+                "(δ count (λ n (set! n (- n 1)) (if (= n 0) 'done (count n)))
+                   (count 100000))"
+            ),
+            "done"
+        );
+    }
+
+    #[test]
+    fn recursive_frames() {
+        let mut runtime = Runtime::new();
+        let mut run = |src: &str| {
+            let before = frame::live();
+            let value = format!("{}", runtime.eval(&parse!(src)).unwrap());
+
+            (value, frame::live().saturating_sub(before))
+        };
+
+        let (value, live) =
+            run("(δ loop (λ n (if (= n 0) 0 (δ g (λ x x) (loop (- n 1))))) (loop 100000))");
+        assert_eq!(value, "0");
+        assert!(live < 1000, "{} frames left", live);
+
+        let (value, live) = run(
+            "(δ loop (λ n (if (= n 0) 0 (letrec ((g (λ x (g x)))) (loop (- n 1))))) (loop 100000))",
+        );
+        assert_eq!(value, "0");
+        assert!(live < 1000, "{} frames left", live);
+    }
+
+    #[test]
+    fn recursion_depth() {
+        // The interpreter runs on a thread with a larger stack than tests have.
+        let on_stack = |f: fn()| {
+            std::thread::Builder::new()
+                .stack_size(crate::STACK_SIZE)
+                .spawn(f)
+                .unwrap()
+                .join()
+                .unwrap()
+        };
+
+        on_stack(|| {
+            let mut runtime = Runtime::new();
+            let count = |n: usize| {
+                parse!(format!(
+                    "(δ f (λ n (if (= n 0) 0 (+ 1 (f (- n 1))))) (f {}))",
+                    n
+                ))
+            };
+
+            assert_eq!(format!("{}", runtime.eval(&count(5000)).unwrap()), "5000");
+            assert_eq!(
+                runtime.eval(&count(MAX_DEPTH)).unwrap_err().kind,
+                ErrorKind::Failure(format!(
+                    "Recursion too deep, over {} nested evaluations",
+                    MAX_DEPTH
+                ))
+            );
+            assert_eq!(format!("{}", runtime.eval(&count(5000)).unwrap()), "5000");
+        });
+
+        on_stack(|| {
+            let nested = |open: &str, close: &str, n: usize| {
+                format!("{}1{}", open.repeat(n), close.repeat(n))
+            };
+
+            for src in [
+                nested("(", ")", 100_000),
+                nested("[", "]", 100_000),
+                nested("'", "", 100_000),
+                nested("'(", ")", 100_000),
+            ] {
+                assert_eq!(
+                    parse_program(&src).unwrap_err().kind,
+                    ErrorKind::Parse(format!("Forms are nested more than {} deep", MAX_NESTING))
+                );
+            }
             assert_eq!(
                 format!(
                     "{}",
-                    Runtime::new().replace_free(&$name.to_string(), &parse!($val), parse!($src))
+                    Runtime::new()
+                        .eval(&parse!(nested("(", ")", MAX_NESTING)))
+                        .unwrap()
                 ),
-                $expected
+                "1"
+            );
+            assert!(parse_program(&nested("'(", ")", MAX_NESTING / 2)).is_ok());
+        });
+    }
+
+    #[test]
+    fn nested_values() {
+        // Values nested more deeply than the stack allows are dropped without recursion.
+        let mut runtime = Runtime::new();
+        for wrap in ["list", "ref"] {
+            let src = format!(
+                "(δ nest (λ n (λ acc (if (= n 0) 0 (nest (- n 1) ({} acc))))) (nest 100000 Φ))",
+                wrap
             );
+
+            assert_eq!(format!("{}", runtime.eval(&parse!(src)).unwrap()), "0");
+        }
+    }
+
+    #[test]
+    fn programs() {
+        let mut runtime = Runtime::new();
+        external::arguments(&mut runtime, vec!["a".to_string(), "b".to_string()]);
+
+        let mut run = |src: &str| runtime.eval_program(&parse_program(src).unwrap());
+
+        assert_eq!(format!("{}", run("(+ 1 2)\n(* 2 3)").unwrap()), "6");
+        assert_eq!(format!("{}", run("").unwrap()), "Φ");
+        assert_eq!(
+            format!("{}", run("(ε argument-count) (ε argument 1)").unwrap()),
+            "\"b\""
+        );
+        assert!(run("(ε argument 2)").is_err());
+        assert!(parse_program("(+ 1 2) )").is_err());
+        assert_eq!(
+            format!("{}", run("(ε string-length \"(a b) c)\")").unwrap()),
+            "8"
+        );
+    }
+
+    #[test]
+    fn definitions() {
+        let mut runtime = Runtime::new();
+        let mut run = |src: &str| runtime.eval_program(&parse_program(src).unwrap());
+
+        assert_eq!(format!("{}", run("(δ x 5)").unwrap()), "5");
+        assert_eq!(
+            format!("{}", run("(δ inc (λ n (+ n x)))").unwrap()),
+            "(λ n (+ n x))"
+        );
+        assert_eq!(format!("{}", run("(inc 1)").unwrap()), "6");
+        assert_eq!(format!("{}", run("(δ x 1 (inc x))").unwrap()), "6");
+        assert_eq!(format!("{}", run("(δ x 2) (inc x)").unwrap()), "4");
+        assert_eq!(format!("{}", run("((λ y (δ z y)) 7) z").unwrap()), "7");
+        assert!(run("(δ y)").is_err());
+        assert!(run("y").is_err());
+
+        // The name of a δ with a body scopes over its value, which cannot read it yet.
+        let err = run("(δ x 1 (δ x (+ x 1) x))").unwrap_err();
+        assert_eq!(
+            format!("{}", err),
+            "1:16: Variable 'x' is used before it is initialised"
+        );
+        assert_eq!(
+            format!("{}", run("(δ x 1 (δ y (+ x 1) (δ x y x)))").unwrap()),
+            "2"
+        );
+        let err = run("(defmacro m (v) `(δ t (+ t 1) (list t ,v))) (δ t 10 (m t))").unwrap_err();
+        assert_eq!(
+            err.kind,
+            ErrorKind::Failure("Variable 't#1' is used before it is initialised".to_string())
+        );
+        assert!(err.span.is_some());
+        assert_eq!(
+            format!(
+                "{}",
+                run("(defmacro m (v) `(δ s (+ ,v 1) (list s ,v))) (δ s 10 (m s))").unwrap()
+            ),
+            "(11 10)"
+        );
+    }
+
+    #[test]
+    fn parameter_lists() {
+        e!("(δ add (λ (a b c) (+ a b c)) (add 1 2 3))", "6");
+        e!("(δ f (λ (a . rest) rest) (f 1 2 3))", "(2 3)");
+        e!("(δ f (λ (a . rest) rest) (f 1))", "()");
+        e!("((λ (. xs) xs) 1 2)", "(1 2)");
+        e!("((λ (_ b) b) 1 2)", "2");
+        e!("(δ f (λ (a b) (λ c (+ a b c))) (f 1 2 3))", "6");
+        e!("(δ f (λ a (λ (b c) (+ a b c))) (f 1 2 3))", "6");
+        e!("(λ (a b . rest) rest)", "(λ (a b . rest) rest)");
+        e!("(λ () 1)", "(λ () 1)");
+        e!("((λ () 1))", "1");
+        e!("(δ f (λ () (+ 1 2)) (f))", "3");
+        e!("(δ f (λ (. xs) xs) (f))", "()");
+        e!("'(λ () 1)", "(λ () 1)");
+        e!("(δ g (λ () (λ y y)) ((g) 4))", "4");
+
+        let err = Runtime::new()
+            .eval(&parse!("(δ add (λ (a b) (+ a b)) (add 1 2 3))"))
+            .unwrap_err();
+        assert_eq!(
+            err.kind,
+            ErrorKind::ArityMismatch {
+                function: "lambda (λ) 'add'".to_string(),
+                expected: "2".to_string(),
+                received: 3
+            }
+        );
+        assert_eq!(
+            format!(
+                "{}",
+                Runtime::new()
+                    .eval(&parse!("((λ (a b . c) a) 1)"))
+                    .unwrap_err()
+            ),
+            "1:1: lambda (λ) expected 2 or more arguments but 1 arguments were provided."
+        );
+        assert!(Runtime::new().eval(&parse!("(λ (a .) a)")).is_err());
+        assert!(Runtime::new().eval(&parse!("(λ (a . b c) a)")).is_err());
+        assert!(Runtime::new().eval(&parse!("(λ (a (b)) a)")).is_err());
+        let err = Runtime::new().eval(&parse!("(λ (a a) a)")).unwrap_err();
+        assert_eq!(
+            format!("{}", err),
+            "1:1: Duplicate name 'a' in lambda (λ) parameters"
+        );
+        assert!(Runtime::new().eval(&parse!("(λ (a . a) a)")).is_err());
+        e!("((λ (_ _ c) c) 1 2 3)", "3");
+        let err = Runtime::new().eval(&parse!("(λ (0 x) x)")).unwrap_err();
+        assert_eq!(
+            format!("{}", err),
+            "1:1: lambda (λ) parameter must be a symbol, not 0"
+        );
+        let err = Runtime::new()
+            .eval(&parse!("((λ ((a) b) a) 1 2)"))
+            .unwrap_err();
+        assert_eq!(
+            format!("{}", err),
+            "1:2: lambda (λ) parameter must be a symbol, not (a)"
+        );
+        assert!(Runtime::new().eval(&parse!("((λ () 1) 2)")).is_err());
+        let err = Runtime::new()
+            .eval(&parse!("(δ add (λ (a b) (+ a b)) (add))"))
+            .unwrap_err();
+        assert_eq!(
+            err.kind,
+            ErrorKind::ArityMismatch {
+                function: "lambda (λ) 'add'".to_string(),
+                expected: "2".to_string(),
+                received: 0
+            }
+        );
+        let err = Runtime::new().eval(&parse!("(car)")).unwrap_err();
+        assert!(matches!(
+            err.kind,
+            ErrorKind::ArityMismatch { received: 0, .. }
+        ));
+        assert!(Runtime::new().eval(&parse!("(δ f (λ x x) (f))")).is_err());
+    }
+
+    #[test]
+    fn match_clauses() {
+        e!(
+            "(δ fib (λ n (match n (0 0) (1 1) (_ (+ (fib (- n 1)) (fib (- n 2)))))) (fib 10))",
+            "55"
+        );
+        e!("(match 7 (x (+ x 1)))", "8");
+        e!(
+            "(match 5 (n when (< n 0) \"negative\") (n \"positive\"))",
+            "\"positive\""
+        );
+        e!(
+            "(δ list (λ (. xs) xs) (match (list 1 (list 2 3)) ((a (b . c)) c)))",
+            "(3)"
+        );
+        e!(
+            "(δ list (λ (. xs) xs) (match (list 1 2) ((a) a) ((a b c) a) ((1 b) b)))",
+            "2"
+        );
+        e!(
+            "(δ list (λ (. xs) xs) (match (list 1) ((a b . c) c) ((a . c) c)))",
+            "()"
+        );
+        e!(
+            "(δ list (λ (. xs) xs) (match (list 1) ((a ()) a) ((a . ()) \"one\")))",
+            "\"one\""
+        );
+        e!("(match Ω (Φ \"nil\"))", "\"nil\"");
+        e!("((match 1 (_ (λ x (+ x 1)))) 2)", "3");
+        e!("(λ n (match n (0 1) (_ n)))", "(λ n (match n (0 1) (_ n)))");
+
+        let err = Runtime::new()
+            .eval(&parse!("(match (+ 1 2) (1 \"one\"))"))
+            .unwrap_err();
+        assert_eq!(err.kind, ErrorKind::NoMatch("3".to_string()));
+        assert_eq!(format!("{}", err), "1:1: match has no clause matching '3'");
+        assert!(Runtime::new()
+            .eval(&parse!("(match 1 (1 when 2))"))
+            .is_err());
+    }
+
+    #[test]
+    fn conditionals() {
+        e!("#t", "#t");
+        e!("(if (< 1 2) \"yes\" \"no\")", "\"yes\"");
+        e!("(if #f 1)", "Φ");
+        e!("(if 0 1 2)", "1");
+        e!("(if Ω 1 2)", "2");
+        e!("((if #t (λ x (+ x 1)) (λ x x)) 1)", "2");
+        e!("(if #t 1 (ε undefined))", "1");
+        e!(
+            "(δ sign (λ n (cond ((< n 0) \"negative\") ((= n 0) \"zero\") (else \"positive\")))
+               (sign 0))",
+            "\"zero\""
+        );
+        e!("(cond (#f 1) (2))", "2");
+        e!("(cond (#f 1))", "Φ");
+        e!("(and 1 2 3)", "3");
+        e!("(and 1 #f (ε undefined))", "#f");
+        e!("(and)", "#t");
+        e!("(or #f Φ 3 (ε undefined))", "3");
+        e!("(or #f Ω)", "Ω");
+        e!("(or)", "#f");
+        e!("(not 1)", "#f");
+        e!("(not (> 1 2))", "#t");
+        e!(
+            "(δ loop (λ n (if (= n 0) \"done\" (loop (- n 1)))) (loop 10000))",
+            "\"done\""
+        );
+
+        assert!(Runtime::new().eval(&parse!("(if #t)")).is_err());
+        assert!(Runtime::new().eval(&parse!("(cond (#t 1 2))")).is_err());
+    }
+
+    #[test]
+    fn lists() {
+        e!("'(1 2 3)", "(1 2 3)");
+        e!("(quote (a (b c) \"d\"))", "(a (b c) \"d\")");
+        e!("'()", "()");
+        e!("'a", "a");
+        e!("''a", "'a");
+        e!("(list)", "()");
+        e!("(list 1 (+ 1 1) 3)", "(1 2 3)");
+        e!("(cons 0 '(1 2))", "(0 1 2)");
+        e!("(car '(1 2))", "1");
+        e!("(cdr '(1 2))", "(2)");
+        e!("(null? '())", "#t");
+        e!("(null? (cdr '(1)))", "#t");
+        e!("(null? '(1))", "#f");
+        e!("(length '(1 2 3))", "3");
+        e!("(append '(1) '() '(2 3))", "(1 2 3)");
+        e!("(map (λ x (* x x)) '(1 2 3))", "(1 4 9)");
+        e!("(filter (λ x (> x 1)) '(1 2 3))", "(2 3)");
+        e!("(fold + 0 '(1 2 3))", "6");
+        e!("(fold (λ (acc x) (cons x acc)) '() '(1 2 3))", "(3 2 1)");
+        e!("(δ f (λ (. xs) (length xs)) (f 1 2))", "2");
+        e!(
+            "(δ sum (λ (acc xs) (if (null? xs) acc (sum (+ acc (car xs)) (cdr xs))))
+               (sum 0 '(1 2 3 4)))",
+            "10"
+        );
+
+        let err = Runtime::new().eval(&parse!("(car '())")).unwrap_err();
+        assert_eq!(format!("{}", err), "1:1: car of an empty list");
+        let err = Runtime::new().eval(&parse!("(cdr 1)")).unwrap_err();
+        assert!(matches!(err.kind, ErrorKind::TypeError { .. }));
+        assert!(Runtime::new()
+            .eval(&parse!("(map (λ x (+ x 1)) '(1 a))"))
+            .is_err());
+    }
+
+    #[test]
+    fn strings() {
+        e!(r#""a\tb\n\"c\" \\ \u{3bb}""#, r#""a\tb\n\"c\" \\ λ""#);
+        e!(r#"(length "a\"λ")"#, "3");
+        e!(r#"(concat "ab" "" "cd")"#, r#""abcd""#);
+        e!("(concat)", r#""""#);
+        e!(r#"(substring "λx.x" 1 3)"#, r#""x.""#);
+        e!(r#"(substring "abc" 1)"#, r#""bc""#);
+        e!(r#"(split "a,b,,c" ",")"#, r#"("a" "b" "" "c")"#);
+        e!(r#"(split "ab" "")"#, r#"("a" "b")"#);
+        e!(r#"(join (split "a b c" " ") ", ")"#, r#""a, b, c""#);
+        e!(r#"(upper "straße")"#, r#""STRASSE""#);
+        e!(r#"(lower "ABC")"#, r#""abc""#);
+        e!(r#"(to-string "a")"#, r#""a""#);
+        e!("(to-string 1.5)", r#""1.5""#);
+        e!(r#"(to-string '(1 "a"))"#, r#""(1 \"a\")""#);
+        e!(r#"(+ (parse-num " 41 ") 1)"#, "42");
+        e!(r#"(parse-num "forty-two")"#, "Φ");
+        e!(r#"(string=? "a" "a" "a")"#, "#t");
+        e!(r#"(string=? "a" "b")"#, "#f");
+
+        for src in [r#""\q""#, r#""\u{}""#, r#""\u{110000}""#, r#""\u{41""#] {
+            assert!(parse_program(src).is_err(), "{}", src);
+        }
+
+        let err = Runtime::new()
+            .eval(&parse!(r#"(substring "abc" 2 4)"#))
+            .unwrap_err();
+        assert_eq!(
+            format!("{}", err),
+            "1:1: substring index 4 is out of range for a string of length 3"
+        );
+        let err = Runtime::new()
+            .eval(&parse!(r#"(substring "abc" 1.0)"#))
+            .unwrap_err();
+        assert!(matches!(err.kind, ErrorKind::TypeError { .. }));
+        let err = Runtime::new()
+            .eval(&parse!(r#"(substring "abc")"#))
+            .unwrap_err();
+        assert_eq!(
+            format!("{}", err),
+            "1:1: substring expected 2 or 3 arguments but 1 arguments were provided."
+        );
+        let err = Runtime::new()
+            .eval(&parse!(r#"(join '("a" 1) "")"#))
+            .unwrap_err();
+        assert!(matches!(err.kind, ErrorKind::TypeError { .. }));
+    }
+
+    #[test]
+    fn symbols_and_chars() {
+        e!(r"#\a", r"#\a");
+        e!(
+            r"'(#\( #\) #\space #\newline #\λ)",
+            r"(#\( #\) #\space #\newline #\λ)"
+        );
+        e!(r#"(to-string #\")"#, r#""\"""#);
+        e!(r"(eq? #\a #\a)", "#t");
+        e!("(eq? 'a 'a)", "#t");
+        e!("(eq? 'a 'b)", "#f");
+        e!("(eq? (car '(a)) (string->symbol \"a\"))", "#t");
+        e!("(symbol->string 'abc)", r#""abc""#);
+        e!("(eq? 'a \"a\")", "#f");
+        e!("(eq? '() '())", "#t");
+        e!("(eq? 1 1.0)", "#f");
+        e!("(δ f (λ x x) (eq? f f))", "#t");
+        e!(
+            "(δ area (λ shape (match shape (('square s) (* s s)) (('rect w h) (* w h))))
+               (+ (area '(square 2)) (area (list 'rect 2 3))))",
+            "10"
+        );
+        e!("(match 'b ('a 1) (x x))", "b");
+        e!("(macroexpand-1 ''(a b))", "'(a b)");
+        e!("`(a ,(symbol->string 'b))", r#"(a "b")"#);
+
+        assert!(Runtime::new()
+            .eval(&parse!("(symbol->string \"a\")"))
+            .is_err());
+        assert!(parse_program(r"#\ab")
+            .and_then(|program| Runtime::new().eval_program(&program))
+            .is_err());
+    }
+
+    #[test]
+    fn maps() {
+        e!(r#"{'a 1 "b" (+ 1 1)}"#, r#"{"b" 2 a 1}"#);
+        e!(r#"'{b 2 a "x"}"#, r#"{a "x" b 2}"#);
+        e!("{}", "{}");
+        e!("(get {'a 1} 'a)", "1");
+        e!("(get {'a 1} 'b)", "Φ");
+        e!("(get {'a 1} 'b 0)", "0");
+        e!("(get {1 'int} 1.0)", "Φ");
+        e!("(get {1/2 'half} 2/4)", "half");
+        e!(
+            "(δ m {'a 1} (list (assoc m 'b 2 'a 3) (dissoc m 'a) m))",
+            "({a 3 b 2} {} {a 1})"
+        );
+        e!("(keys '{b 2 a 1})", "(a b)");
+        e!("(vals '{b 2 a 1})", "(1 2)");
+        e!("(contains? '{a Φ} 'a)", "#t");
+        e!("(contains? '{a 1} 'b)", "#f");
+        e!("(merge '{a 1 b 2} '{b 3} '{c 4})", "{a 1 b 3 c 4}");
+        e!("(length '{a 1 b 2})", "2");
+
+        let map = format!(
+            "{}",
+            Runtime::new().eval(&parse!("'{b (1 2) a \"x\"}")).unwrap()
+        );
+        e!(&format!("'{}", map), map);
+
+        assert!(parse_program("{'a}").is_err());
+        assert!(parse_program("'{(a) 1}").is_err());
+        assert!(Runtime::new().eval(&parse!("{(list 1) 2}")).is_err());
+        assert!(Runtime::new().eval(&parse!("(assoc {} 'a)")).is_err());
+        let err = Runtime::new().eval(&parse!("(get '(a 1) 'a)")).unwrap_err();
+        assert!(matches!(err.kind, ErrorKind::TypeError { .. }));
+    }
+
+    #[test]
+    fn vectors() {
+        e!("[1 (+ 1 1) 'a]", "[1 2 a]");
+        e!("'[a [b] (c)]", "[a [b] (c)]");
+        e!("[]", "[]");
+        e!("(nth [1 2 3] 0)", "1");
+        e!("(nth '[a b c] 2)", "c");
+        e!("(δ v [1 2] (list (push v 3 4) v))", "([1 2 3 4] [1 2])");
+        e!("(slice [1 2 3 4] 1 3)", "[2 3]");
+        e!("(slice [1 2 3 4] 2)", "[3 4]");
+        e!("(slice [1 2] 2)", "[]");
+        e!("(len [1 2 3])", "3");
+        e!("(length [1 2 3])", "3");
+        e!("(vector->list [1 [2] 3])", "(1 [2] 3)");
+        e!("(λ x [x 1])", "(λ x [x 1])");
+
+        let vector = format!(
+            "{}",
+            Runtime::new()
+                .eval(&parse!("'[b (1 2) \"x\" {a 1}]"))
+                .unwrap()
+        );
+        e!(&format!("'{}", vector), vector);
+
+        let err = Runtime::new().eval(&parse!("(nth [1 2 3] 3)")).unwrap_err();
+        assert!(matches!(
+            err.kind,
+            ErrorKind::IndexOutOfRange { length: 3, .. }
+        ));
+        assert_eq!(
+            format!("{}", err),
+            "1:1: nth index 3 is out of range for a vector of length 3"
+        );
+        let err = Runtime::new().eval(&parse!("(nth [1] -1)")).unwrap_err();
+        assert!(matches!(err.kind, ErrorKind::IndexOutOfRange { .. }));
+        let err = Runtime::new()
+            .eval(&parse!("(nth [1 2] 9999999999999999999999)"))
+            .unwrap_err();
+        assert!(matches!(
+            err.kind,
+            ErrorKind::IndexOutOfRange { length: 2, .. }
+        ));
+        let err = Runtime::new().eval(&parse!("(nth [1 2] 0.5)")).unwrap_err();
+        assert_eq!(
+            format!("{}", err),
+            "1:1: nth expected an integer but received '0.5'"
+        );
+        let err = Runtime::new()
+            .eval(&parse!("(slice [1 2] 1 3)"))
+            .unwrap_err();
+        assert!(matches!(err.kind, ErrorKind::IndexOutOfRange { .. }));
+        assert!(Runtime::new().eval(&parse!("(slice [1 2] 2 1)")).is_err());
+        let err = Runtime::new().eval(&parse!("(nth '(1 2) 0)")).unwrap_err();
+        assert!(matches!(err.kind, ErrorKind::TypeError { .. }));
+        assert!(parse_program("[1 2").is_err());
+    }
+
+    #[test]
+    fn mutation() {
+        e!(
+            "(δ counter (λ _ (δ n 0 (λ _ (set! n (+ n 1)))))
+               (δ c (counter 0) (δ d (counter 0) (list (c 0) (c 0) (d 0) (c 0)))))",
+            "(1 2 1 3)"
+        );
+        e!(
+            "(δ x 1 (δ g (λ _ x) (δ x 2 (δ y (set! x 3) (list x (g 0))))))",
+            "(3 1)"
+        );
+        e!("(δ x 1 (δ g (λ _ x) (δ y (set! x 3) (g 0))))", "3");
+        e!(
+            "(δ r (ref 1) (δ y (set-ref! r (+ (deref r) 1)) (deref r)))",
+            "2"
+        );
+        e!(
+            "(δ acc (ref '()) (δ push (λ x (set-ref! acc (cons x (deref acc))))
+               (δ y (push 1) (δ z (push 2) (deref acc)))))",
+            "(2 1)"
+        );
+        e!("(ref '(1 2))", "(ref (1 2))");
+        e!("(δ r (ref 1) (list (eq? r r) (eq? r (ref 1))))", "(#t #f)");
+        e!("(δ r (ref 0) (set-ref! r r))", "(ref (ref ...))");
+
+        let program = parse_program("(δ x 1) (δ f (λ _ x)) (set! x 5) (f 0)").unwrap();
+        assert_eq!(
+            format!("{}", Runtime::new().eval_program(&program).unwrap()),
+            "5"
+        );
+
+        let err = Runtime::new().eval(&parse!("(set! x 1)")).unwrap_err();
+        assert_eq!(err.kind, ErrorKind::UnboundVariable("x".to_string()));
+        assert!(Runtime::new().eval(&parse!("(set! 1 1)")).is_err());
+        assert!(Runtime::new().eval(&parse!("(δ x 1 (set! x))")).is_err());
+        let err = Runtime::new().eval(&parse!("(deref 1)")).unwrap_err();
+        assert!(matches!(err.kind, ErrorKind::TypeError { .. }));
+    }
+
+    #[test]
+    fn sequencing() {
+        e!("(begin 1 2 3)", "3");
+        e!("(do (+ 1 1))", "2");
+        e!("(begin)", "Φ");
+        e!(
+            "(δ r (ref 0) (begin (set-ref! r 1) (set-ref! r (+ (deref r) 1)) (deref r)))",
+            "2"
+        );
+        e!("((λ x (set! x (+ x 1)) (* x 2)) 3)", "8");
+        e!("(δ x 1 (set! x 2) (+ x 1))", "3");
+        e!("(δ f (λ x (set! x 0) x) (f 5))", "0");
+        e!("(λ x 1 x)", "(λ x 1 x)");
+        e!("((λ x (+ x 1)) 2)", "3");
+        e!("((λ (a b) a b) 1 2)", "2");
+        e!("((begin +) 1 2)", "3");
+        e!("((δ f (λ x x) f) 5)", "5");
+        e!("(δ add (λ a (λ b (+ a b))) ((add 1) 2))", "3");
+
+        assert_eq!(format!("{}", parse!("((λ x x) 1)")), "((λ x x) 1)");
+        assert_eq!(format!("{}", parse!("((f x) 1)")), "(f x 1)");
+        assert!(Runtime::new().eval(&parse!("(λ x)")).is_err());
+        assert!(Runtime::new().eval(&parse!("(begin (car 1) 2)")).is_err());
+    }
+
+    #[test]
+    fn let_forms() {
+        e!("(let ((a 1) (b 2)) (+ a b))", "3");
+        e!("(δ a 1 (let ((a 2) (b a)) (list a b)))", "(2 1)");
+        e!("(let* ((a 1) (b (+ a 1))) (list a b))", "(1 2)");
+        e!("(let* ((x 1) (x (+ x 1))) x)", "2");
+        e!("(δ y 0 (let* ((f (λ _ y)) (y 5)) (list y (f 0))))", "(5 0)");
+        e!(
+            "(letrec ((even? (λ n (if (= n 0) #t (odd? (- n 1)))))
+                      (odd? (λ n (if (= n 0) #f (even? (- n 1))))))
+               (list (even? 10) (odd? 7)))",
+            "(#t #t)"
+        );
+        e!("(let ((r (ref 0))) (set-ref! r 5) (deref r))", "5");
+        e!("(let () 1)", "1");
+        e!("((let ((a 1)) (λ b (+ a b))) 2)", "3");
+        e!("'(let ((a 1)) a)", "(let ((a 1)) a)");
+        e!("(macroexpand '(let ((a 1)) a))", "(let ((a 1)) a)");
+
+        assert_eq!(
+            format!("{}", parse!("(let* ((a 1) (b a)) b)")),
+            "(let* ((a 1) (b a)) b)"
+        );
+
+        let run = |src: &str| {
+            parse_program(src).and_then(|program| Runtime::new().eval_program(&program))
         };
+        let add = "(defmacro add (a b) `(let ((t ,a)) (+ t ,b)))";
+        assert_eq!(
+            format!("{}", run(&format!("{} (δ t 10 (add 1 t))", add)).unwrap()),
+            "11"
+        );
+        assert_eq!(
+            format!(
+                "{}",
+                run(&format!("{} (macroexpand '(add x y))", add)).unwrap()
+            ),
+            "(let ((t#1 x)) (+ t#1 y))"
+        );
+
+        assert!(parse_program("(let (a 1) a)").is_err());
+        assert!(Runtime::new().eval(&parse!("(let ((1 2)) 1)")).is_err());
+        let err = Runtime::new().eval(&parse!("(let ((a 1)))")).unwrap_err();
+        assert!(matches!(err.kind, ErrorKind::ArityMismatch { .. }));
+        let err = Runtime::new()
+            .eval(&parse!("(let ((a b) (b 1)) a)"))
+            .unwrap_err();
+        assert_eq!(err.kind, ErrorKind::UnboundVariable("b".to_string()));
+
+        let err = run("(δ b 99) (letrec ((a b) (b 1)) a)").unwrap_err();
+        assert_eq!(
+            err.kind,
+            ErrorKind::Failure("Variable 'b' is used before it is initialised".to_string())
+        );
+        e!("(letrec ((a 1) (b (+ a 1))) b)", "2");
+        let err = Runtime::new()
+            .eval(&parse!("(let ((x 1) (x 2)) x)"))
+            .unwrap_err();
+        assert_eq!(
+            err.kind,
+            ErrorKind::Failure("Duplicate name 'x' in let bindings".to_string())
+        );
+        assert!(Runtime::new()
+            .eval(&parse!("(letrec ((x 1) (x 2)) x)"))
+            .is_err());
     }
 
     #[test]
-    fn replace_free_lambda() {
-        t!("(x (λ x x) x)", "x", "1", "(1 (λ x x) 1)");
-        t!(
-            "(f (λ f (f x)) 5)",
-            "f",
-            "(λ _ 5)",
-            "((λ _ 5) (λ f (f x)) 5)"
+    fn quasiquote() {
+        e!("`(1 ,(+ 1 1) ,@(list 3 4))", "(1 2 3 4)");
+        e!("`x", "x");
+        e!("`(,@'() 1)", "(1)");
+        e!("(δ x 5 `(x ,x (,x)))", "(x 5 (5))");
+        e!("(quasiquote (1 (unquote (+ 1 1))))", "(1 2)");
+        e!("`(1 `(2 ,(3 ,(+ 1 3))))", "(1 `(2 ,(3 4)))");
+        e!("'(a ,b ,@c `d)", "(a ,b ,@c `d)");
+        e!("(λ x `(a ,x))", "(λ x `(a ,x))");
+        e!("(λ x '(a b))", "(λ x '(a b))");
+        e!(
+            "`(,(match (list 1 2) ((a b) (+ a b))) ,(cond (#f 1) (else '(a))))",
+            "(3 (a))"
         );
+
+        e!("(δ x 5 `[a ,x ,@(list 1 2) [,x]])", "[a 5 1 2 [5]]");
+        e!("(δ x 5 `{a ,x b (,x)})", "{a 5 b (5)}");
+        e!("'[a ,x]", "[a ,x]");
+        assert!(Runtime::new().eval(&parse!("`{a ,@(list 1)}")).is_err());
+
+        assert!(Runtime::new().eval(&parse!(",x")).is_err());
+        assert!(Runtime::new().eval(&parse!("`,@(list 1)")).is_err());
+        let err = Runtime::new().eval(&parse!("`(,@1)")).unwrap_err();
+        assert!(matches!(err.kind, ErrorKind::TypeError { .. }));
     }
 
     #[test]
-    fn replace_free_def() {
-        t!("(x (δ x x) y x)", "x", "1", "(1 (δ x x) y 1)");
-        t!("(δ f (λ x y x))", "y", "ι", "(δ f (λ x ι x))");
-        t!(
-            "(δ true (λ p (λ q p))
-      (δ false (λ p (λ q q))
-        true
-      )
-    )",
-            "p",
-            "42",
-            "(δ true (λ p (λ q p)) (δ false (λ p (λ q q)) true))"
+    fn macros() {
+        let run = |src: &str| {
+            parse_program(src).and_then(|program| Runtime::new().eval_program(&program))
+        };
+        let r = |src: &str| format!("{}", run(src).unwrap());
+
+        let unless = "(defmacro unless (c a b) `(if ,c ,b ,a))";
+        assert_eq!(r(&format!("{} (unless #f 1 2)", unless)), "1");
+        assert_eq!(r(&format!("{} (unless #f (unless #t 1 2) 3)", unless)), "2");
+        assert_eq!(
+            r(&format!("{} ((unless #f (λ x (* x 2)) Ω) 21)", unless)),
+            "42"
+        );
+        assert_eq!(
+            r("(defmacro my-let (x v body) `((λ ,x ,body) ,v)) (my-let y 2 (* y y))"),
+            "4"
+        );
+        assert_eq!(r("(defmacro name-of x `',x) (name-of (+ 1 2))"), "(+ 1 2)");
+        assert_eq!(r("(defmacro five _ 5) (+ (five) 1)"), "6");
+        assert_eq!(r("(defmacro inc (x) `(+ ,x 1)) (inc 5)"), "6");
+
+        // The template is evaluated once, at expansion time, to build the code.
+        assert_eq!(r("(defmacro sum (. xs) (cons '+ xs)) (sum 1 2 3)"), "6");
+        assert_eq!(
+            r("(defmacro when (c . body) `(if ,c (begin ,@body) Φ)) (when #t 1 2)"),
+            "2"
+        );
+        assert_eq!(
+            r("(defmacro when (c . body) `(if ,c (begin ,@body) Φ)) (macroexpand '(when x a b))"),
+            "(if x (begin a b) Φ)"
         );
+        assert_eq!(
+            r("(defmacro first-of (x . more) (car x)) (first-of (+ 1 2) 3)"),
+            "+"
+        );
+        assert_eq!(
+            r("(defmacro m (x) (if (eq? x 'a) 1 2)) (list (m a) (m b))"),
+            "(1 2)"
+        );
+        assert_eq!(r("(defmacro v (x) `[,x 1]) (v 5)"), "[5 1]");
+        assert_eq!(r("(defmacro inc (x) `(+ ,x 1)) `[a ,(inc 1)]"), "[a 2]");
+
+        // Unquoted code is expanded, but not calls of a local variable named like a macro.
+        let m = "(defmacro m (x) `(+ ,x 1))";
+        assert_eq!(r(&format!("{} `(a ,(m 1) (m 1))", m)), "(a 2 (m 1))");
+        assert_eq!(
+            r(&format!("{} (list (δ m (λ y 5) (m 1)) (m 1))", m)),
+            "(5 2)"
+        );
+        assert_eq!(r(&format!("{} ((λ m (m 1)) (λ y 5))", m)), "5");
+        assert_eq!(r(&format!("{} (let ((m (λ y 5))) (m 1))", m)), "5");
+        assert_eq!(
+            r(&format!("{} (let* ((a (m 1)) (m (λ y a))) (m 1))", m)),
+            "2"
+        );
+        assert_eq!(r(&format!("{} (match (λ y 5) (m (m 1)))", m)), "5");
+        assert_eq!(r(&format!("{} ((λ (m y) (+ m y)) 1 2)", m)), "3");
+
+        // The variable bound by the template does not capture the one passed in.
+        let or2 = "(defmacro or2 (a b) `(δ t ,a (if t t ,b)))";
+        assert_eq!(r(&format!("{} (δ t 5 (or2 #f t))", or2)), "5");
+        assert_eq!(r(&format!("{} (δ t#1 5 (or2 #f t#1))", or2)), "5");
+        assert_eq!(
+            r(&format!("{} (macroexpand '(or2 x y))", or2)),
+            "(δ t#1 x (if t#1 t#1 y))"
+        );
+        let swap = "(defmacro swap (pair) `(match ,pair ((a b) (list b a))))";
+        assert_eq!(r(&format!("{} (δ a 1 (swap (list 2 a)))", swap)), "(1 2)");
+
+        let when_not = format!("{} (defmacro when-not (c a) `(unless ,c ,a Ω))", unless);
+        assert_eq!(
+            r(&format!("{} (macroexpand-1 '(when-not x 1))", when_not)),
+            "(unless x 1 Ω)"
+        );
+        assert_eq!(
+            r(&format!("{} (macroexpand '(when-not x 1))", when_not)),
+            "(if x Ω 1)"
+        );
+        assert_eq!(
+            r(&format!("{} (macroexpand '(+ 1 2))", when_not)),
+            "(+ 1 2)"
+        );
+
+        let err = run(&format!("{} (unless #t)", unless)).unwrap_err();
+        assert!(matches!(err.kind, ErrorKind::ArityMismatch { .. }));
+        assert!(run("(defmacro 1 x x)").is_err());
+        assert!(run("(defmacro m (1) 1)").is_err());
+        assert!(run("(defmacro m (a . b c) 1)").is_err());
+        let err = run("(if #f (defmacro m (x) `(+ ,x 1)) 0) (m 1)").unwrap_err();
+        assert_eq!(err.kind, ErrorKind::UnboundVariable("m".to_string()));
+        assert_eq!(r("(if #t (defmacro m (x) `(+ ,x 1)) 0) (m 1)"), "2");
+        let err = run("(defmacro m (a . b) a) (m)").unwrap_err();
+        assert!(matches!(err.kind, ErrorKind::ArityMismatch { .. }));
+    }
+
+    #[test]
+    fn errors_propagate() {
+        let err = Runtime::new()
+            .eval(&parse!("(δ f (λ x (+ x y)) (f 1))"))
+            .unwrap_err();
+        assert_eq!(err.kind, ErrorKind::UnboundVariable("y".to_string()));
+        assert_eq!(
+            err.span,
+            Some(Span::new(Position::new(0, 15), Position::new(0, 16)))
+        );
+
+        let err = Runtime::new()
+            .eval(&parse!("(-> 1 (λ 0 \"zero\"))"))
+            .unwrap_err();
+        assert!(matches!(err.kind, ErrorKind::PatternMismatch { .. }));
+        let err = Runtime::new()
+            .eval(&parse!("(δ zero? (λ 0 #t) (zero? 1))"))
+            .unwrap_err();
+        assert_eq!(
+            format!("{}", err),
+            "1:19: lambda (λ) 'zero?' expected '0' but received '1'"
+        );
+
+        let err = Runtime::new().eval(&parse!("(+ 1 \"2\")")).unwrap_err();
+        assert!(matches!(err.kind, ErrorKind::TypeError { .. }));
+        assert_eq!(
+            format!("{}", err),
+            "1:1: + expected a number but received '\"2\"'"
+        );
+
+        let err = Runtime::new().eval(&parse!("(ε sqrt 1 2)")).unwrap_err();
+        assert!(matches!(err.kind, ErrorKind::ArityMismatch { .. }));
+
+        e!("(_ 1 2)", "2");
+        e!("(_ 1 (ε sqrt) 4)", "2");
+    }
+
+    #[test]
+    fn no_panics() {
+        for src in [
+            "(_ 1 2)",
+            "(λ)",
+            "(λ x)",
+            "(λ (x) x)",
+            "(δ)",
+            "(δ 1 2 3)",
+            "(ε)",
+            "(ε 5)",
+            "(->)",
+            "λ",
+            "(1 2)",
+            "(1 2 3)",
+            ")",
+            "(",
+            "((",
+            "(ι",
+            "\"abc",
+            "(ε missing)",
+            "(5 5)",
+            "(δ 1 x)",
+            "((λ x x))",
+            "(Ω 1 2)",
+            "(ι ι ι)",
+        ] {
+            let _ = parse_program(src).and_then(|program| Runtime::new().eval_program(&program));
+        }
+    }
+
+    /// Checks `replace_free` against substitution on de Bruijn terms, where binders have
+    /// no names to capture and α-equivalent terms are equal.
+    mod de_bruijn {
+        use proptest::prelude::*;
+
+        use crate::{evaluator::Runtime, keywords::Keyword, parser::Expr};
+
+        #[derive(Debug, Clone, PartialEq)]
+        enum Term {
+            Free(String),
+            /// The binder counted outwards, then the variable within the binder.
+            Bound(usize, usize),
+            Lambda(Box<Term>),
+            /// A δ binds its name in both its value and its body.
+            Def(Box<Term>, Box<Term>),
+            Match(Box<Term>, Box<Term>),
+            Application(Box<Term>, Box<Term>),
+        }
+
+        fn names(binder: &Expr) -> Vec<String> {
+            match binder {
+                Expr::Var { name, .. } => vec![name.clone()],
+                Expr::List(items) => items.iter().flat_map(names).collect(),
+                _ => vec![],
+            }
+        }
+
+        fn term(expr: &Expr, scopes: &mut Vec<Vec<String>>) -> Term {
+            let bind = |binder: &Expr, scope: &[&Expr], scopes: &mut Vec<Vec<String>>| {
+                scopes.push(names(binder));
+                let terms = scope
+                    .iter()
+                    .map(|expr| term(expr, scopes))
+                    .collect::<Vec<Term>>();
+                scopes.pop();
+
+                terms
+            };
+
+            match expr {
+                Expr::Var { name, .. } => scopes
+                    .iter()
+                    .rev()
+                    .enumerate()
+                    .find_map(|(i, scope)| {
+                        scope
+                            .iter()
+                            .rposition(|bound| bound == name)
+                            .map(|j| Term::Bound(i, j))
+                    })
+                    .unwrap_or_else(|| Term::Free(name.clone())),
+                Expr::Expr {
+                    operator, operands, ..
+                } => match (operator.as_ref(), &operands[..]) {
+                    (Expr::Keyword(Keyword::Lambda), [parameter, body]) => {
+                        Term::Lambda(Box::new(bind(parameter, &[body], scopes).remove(0)))
+                    }
+                    (Expr::Keyword(Keyword::Def), [name, value, body]) => {
+                        let mut terms = bind(name, &[value, body], scopes);
+                        let body = terms.pop().unwrap();
+
+                        Term::Def(Box::new(terms.pop().unwrap()), Box::new(body))
+                    }
+                    (Expr::Keyword(Keyword::Match), [scrutinee, Expr::List(clause)]) => {
+                        let parts = clause.iter().collect::<Vec<&Expr>>();
+
+                        Term::Match(
+                            Box::new(term(scrutinee, scopes)),
+                            Box::new(bind(parts[0], &[parts[1]], scopes).remove(0)),
+                        )
+                    }
+                    (function, [argument]) => Term::Application(
+                        Box::new(term(function, scopes)),
+                        Box::new(term(argument, scopes)),
+                    ),
+                    _ => unreachable!("not generated: {}", expr),
+                },
+                _ => unreachable!("not generated: {}", expr),
+            }
+        }
+
+        /// The substituted value has no bound variables pointing outside of it, so unlike
+        /// a general de Bruijn substitution this needs no shifting.
+        fn substitute(term: Term, name: &str, value: &Term) -> Term {
+            let go = |term: Box<Term>| Box::new(substitute(*term, name, value));
+
+            match term {
+                Term::Free(free) if free == name => value.clone(),
+                Term::Lambda(body) => Term::Lambda(go(body)),
+                Term::Def(value, body) => Term::Def(go(value), go(body)),
+                Term::Match(scrutinee, body) => Term::Match(go(scrutinee), go(body)),
+                Term::Application(function, argument) => {
+                    Term::Application(go(function), go(argument))
+                }
+                term => term,
+            }
+        }
+
+        fn form(keyword: Keyword, operands: Vec<Expr>) -> Expr {
+            Expr::Expr {
+                operator: Box::new(Expr::Keyword(keyword)),
+                operands,
+                span: None,
+            }
+        }
+
+        fn var() -> impl Strategy<Value = Expr> {
+            // `x'` collides with the names chosen when renaming `x`.
+            prop::sample::select(vec!["x", "y", "z", "x'"]).prop_map(|name| Expr::Var {
+                name: name.to_string(),
+                span: None,
+            })
+        }
+
+        fn expr() -> impl Strategy<Value = Expr> {
+            var().prop_recursive(6, 64, 3, |inner| {
+                prop_oneof![
+                    (var(), inner.clone())
+                        .prop_map(|(parameter, body)| form(Keyword::Lambda, vec![parameter, body])),
+                    (var(), inner.clone(), inner.clone()).prop_map(|(name, value, body)| form(
+                        Keyword::Def,
+                        vec![name, value, body]
+                    )),
+                    (inner.clone(), var(), var(), inner.clone())
+                        .prop_filter("distinct pattern variables", |(_, a, b, _)| names(a)
+                            != names(b))
+                        .prop_map(|(scrutinee, a, b, body)| {
+                            let pattern = Expr::List([a, b].into_iter().collect());
+
+                            form(
+                                Keyword::Match,
+                                vec![scrutinee, Expr::List([pattern, body].into_iter().collect())],
+                            )
+                        }),
+                    (inner.clone(), inner).prop_map(|(function, argument)| Expr::Expr {
+                        operator: Box::new(function),
+                        operands: vec![argument],
+                        span: None,
+                    }),
+                ]
+            })
+        }
+
+        proptest! {
+            #[test]
+            fn replace_free_matches_de_bruijn(ast in expr(), name in var(), value in expr()) {
+                let name = names(&name).remove(0);
+                let replaced = Runtime::new().replace_free(&name, &value, ast.clone());
+
+                prop_assert_eq!(
+                    term(&replaced, &mut vec![]),
+                    substitute(term(&ast, &mut vec![]), &name, &term(&value, &mut vec![]))
+                );
+            }
+        }
     }
 }
@@ -0,0 +1,250 @@
+use std::{fmt::Debug, io::BufRead, rc::Rc};
+
+use crate::{error::EvalError, evaluator::Runtime, literal::Literal, number::Number, parser::Expr};
+
+pub type NativeFn = Rc<dyn Fn(&[Expr]) -> Result<Expr, EvalError>>;
+
+/// A host function reachable from programs through `(ε name ...)`.
+#[derive(Clone)]
+pub struct External {
+    name: String,
+    arity: usize,
+    function: NativeFn,
+}
+
+impl Debug for External {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_fmt(format_args!("External({}/{})", self.name, self.arity))
+    }
+}
+
+impl External {
+    pub fn new<Args, F: IntoExternal<Args>>(name: &str, function: F) -> Self {
+        Self {
+            name: name.to_string(),
+            arity: F::arity(),
+            function: function.into_native(format!("external (ε) '{}'", name)),
+        }
+    }
+
+    pub fn arity(&self) -> usize {
+        self.arity
+    }
+
+    pub fn call(&self, arguments: &[Expr]) -> Result<Expr, EvalError> {
+        if arguments.len() != self.arity {
+            return Err(EvalError::arity(
+                format!("external (ε) '{}'", self.name),
+                format!("{}", self.arity),
+                arguments.len(),
+            ));
+        }
+
+        (self.function)(arguments)
+    }
+}
+
+pub trait FromExpr: Sized {
+    /// Describes the accepted values in type errors, e.g. "a number".
+    const EXPECTED: &'static str;
+
+    fn from_expr(expr: &Expr) -> Option<Self>;
+}
+
+impl FromExpr for Expr {
+    const EXPECTED: &'static str = "a value";
+
+    fn from_expr(expr: &Expr) -> Option<Self> {
+        Some(expr.clone())
+    }
+}
+
+impl FromExpr for Number {
+    const EXPECTED: &'static str = "a number";
+
+    fn from_expr(expr: &Expr) -> Option<Self> {
+        match expr {
+            Expr::Literal(Literal::Num(num)) => Some(num.clone()),
+            _ => None,
+        }
+    }
+}
+
+impl FromExpr for f64 {
+    const EXPECTED: &'static str = "a number";
+
+    fn from_expr(expr: &Expr) -> Option<Self> {
+        Number::from_expr(expr).map(|num| num.to_f64())
+    }
+}
+
+impl FromExpr for i64 {
+    const EXPECTED: &'static str = "an integer";
+
+    fn from_expr(expr: &Expr) -> Option<Self> {
+        match expr {
+            Expr::Literal(Literal::Num(Number::Int(num))) => Some(*num),
+            _ => None,
+        }
+    }
+}
+
+impl FromExpr for bool {
+    const EXPECTED: &'static str = "a boolean";
+
+    fn from_expr(expr: &Expr) -> Option<Self> {
+        match expr {
+            Expr::Literal(Literal::Bool(value)) => Some(*value),
+            _ => None,
+        }
+    }
+}
+
+impl FromExpr for String {
+    const EXPECTED: &'static str = "a string";
+
+    fn from_expr(expr: &Expr) -> Option<Self> {
+        match expr {
+            Expr::Literal(Literal::String(string)) => Some(string.clone()),
+            _ => None,
+        }
+    }
+}
+
+pub trait IntoExpr {
+    fn into_expr(self) -> Result<Expr, EvalError>;
+}
+
+impl IntoExpr for Expr {
+    fn into_expr(self) -> Result<Expr, EvalError> {
+        Ok(self)
+    }
+}
+
+impl IntoExpr for () {
+    fn into_expr(self) -> Result<Expr, EvalError> {
+        Ok(Expr::Literal(Literal::Nil))
+    }
+}
+
+impl IntoExpr for Number {
+    fn into_expr(self) -> Result<Expr, EvalError> {
+        Ok(Expr::Literal(Literal::Num(self)))
+    }
+}
+
+impl IntoExpr for f64 {
+    fn into_expr(self) -> Result<Expr, EvalError> {
+        Number::Float(self).into_expr()
+    }
+}
+
+impl IntoExpr for i64 {
+    fn into_expr(self) -> Result<Expr, EvalError> {
+        Number::Int(self).into_expr()
+    }
+}
+
+impl IntoExpr for bool {
+    fn into_expr(self) -> Result<Expr, EvalError> {
+        Ok(Expr::Literal(Literal::Bool(self)))
+    }
+}
+
+impl IntoExpr for String {
+    fn into_expr(self) -> Result<Expr, EvalError> {
+        Ok(Expr::Literal(Literal::String(self)))
+    }
+}
+
+impl<T: IntoExpr> IntoExpr for Result<T, String> {
+    fn into_expr(self) -> Result<Expr, EvalError> {
+        self.map_err(EvalError::failure)
+            .and_then(|value| value.into_expr())
+    }
+}
+
+/// Closures with typed arguments that can be registered as externals.
+pub trait IntoExternal<Args> {
+    fn arity() -> usize;
+    fn into_native(self, name: String) -> NativeFn;
+}
+
+macro_rules! into_external {
+    ($arity:expr; $($arg:ident),*) => {
+        impl<F, R, $($arg),*> IntoExternal<($($arg,)*)> for F
+        where
+            F: Fn($($arg),*) -> R + 'static,
+            R: IntoExpr,
+            $($arg: FromExpr),*
+        {
+            fn arity() -> usize {
+                $arity
+            }
+
+            #[allow(non_snake_case, unused_variables, unused_mut)]
+            fn into_native(self, name: String) -> NativeFn {
+                Rc::new(move |arguments: &[Expr]| {
+                    let mut arguments = arguments.iter();
+                    $(
+                        let argument = arguments.next().unwrap();
+                        let $arg = $arg::from_expr(argument)
+                            .ok_or_else(|| EvalError::type_error(name.clone(), $arg::EXPECTED, argument))?;
+                    )*
+
+                    (self)($($arg),*).into_expr()
+                })
+            }
+        }
+    };
+}
+
+into_external!(0;);
+into_external!(1; A);
+into_external!(2; A, B);
+into_external!(3; A, B, C);
+
+pub fn prelude(runtime: &mut Runtime) {
+    runtime.register_external("print", |e: Expr| match e {
+        Expr::Literal(Literal::String(s)) => print!("{}", s),
+        e => print!("{}", e),
+    });
+    runtime.register_external("println", |e: Expr| match e {
+        Expr::Literal(Literal::String(s)) => println!("{}", s),
+        e => println!("{}", e),
+    });
+    runtime.register_external("read-line", || {
+        let mut line = String::new();
+
+        std::io::stdin()
+            .lock()
+            .read_line(&mut line)
+            .map(|_| line.trim_end_matches('\n').to_string())
+            .map_err(|err| err.to_string())
+    });
+    runtime.register_external("sqrt", |n: Number| n.sqrt());
+    runtime.register_external("pow", |base: Number, exponent: Number| base.pow(&exponent));
+    runtime.register_external("exp", f64::exp);
+    runtime.register_external("ln", f64::ln);
+    runtime.register_external("sin", f64::sin);
+    runtime.register_external("cos", f64::cos);
+    runtime.register_external("string-length", |s: String| s.chars().count() as i64);
+}
+
+/// Exposes the command line arguments of a script as `(ε argument n)` and `(ε argument-count)`.
+pub fn arguments(runtime: &mut Runtime, arguments: Vec<String>) {
+    let arguments = Rc::new(arguments);
+    let count = arguments.len() as i64;
+
+    runtime.register_external("argument-count", move || count);
+    runtime.register_external("argument", move |n: i64| {
+        match usize::try_from(n).ok().and_then(|n| arguments.get(n)) {
+            Some(argument) => Ok(argument.clone()),
+            _ => Err(format!(
+                "argument {} is out of range, {} arguments were provided",
+                n,
+                arguments.len()
+            )),
+        }
+    });
+}
@@ -1,28 +1,162 @@
-use std::{collections::HashMap, ops::Index};
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    rc::{Rc, Weak},
+};
 
 use crate::parser::Expr;
 
-#[derive(Debug)]
+pub type Env = Rc<RefCell<Frame>>;
+
+#[cfg(test)]
+thread_local! {
+    /// The number of frames allocated and not yet freed on this thread.
+    static LIVE: std::cell::Cell<usize> = const { std::cell::Cell::new(0) };
+}
+
+#[cfg(test)]
+pub fn live() -> usize {
+    LIVE.with(|live| live.get())
+}
+
+#[derive(Debug, Default)]
 pub struct Frame {
-    variables: HashMap<String, Expr>,
+    /// `None` for the names a `letrec` binds before their values are evaluated.
+    variables: HashMap<String, Option<Expr>>,
+    parent: Option<Env>,
 }
 
 impl Frame {
-    pub fn new(name: String, value: Expr) -> Self {
-        let mut s = Self {
+    pub fn new(parent: Option<Env>) -> Env {
+        #[cfg(test)]
+        LIVE.with(|live| live.set(live.get() + 1));
+
+        Rc::new(RefCell::new(Self {
             variables: HashMap::new(),
-        };
+            parent,
+        }))
+    }
+
+    /// The value of the nearest binding of `name`, which is `Some(None)` if the binding is
+    /// not initialised yet.
+    pub fn lookup(&self, name: &str) -> Option<Option<Expr>> {
+        match self.variables.get(name) {
+            Some(value) => Some(value.clone()),
+            None => self
+                .parent
+                .as_ref()
+                .and_then(|parent| parent.borrow().lookup(name)),
+        }
+    }
 
-        s.variables.insert(name, value);
+    pub fn push(&mut self, name: String, value: Expr) {
+        self.variables.insert(name, Some(value));
+    }
 
-        s
+    /// Binds `name` without a value, so reading it fails until a value is pushed.
+    pub fn declare(&mut self, name: String) {
+        self.variables.insert(name, None);
     }
 
-    pub fn lookup(&self, name: &String) -> Option<&Expr> {
-        self.variables.get(name)
+    /// Replaces the value of the nearest binding of `name`, returning whether there is one.
+    pub fn set(&mut self, name: &str, value: Expr) -> bool {
+        match self.variables.get_mut(name) {
+            Some(variable) => {
+                *variable = Some(value);
+
+                true
+            }
+            None => self
+                .parent
+                .as_ref()
+                .is_some_and(|parent| parent.borrow_mut().set(name, value)),
+        }
     }
+}
 
-    pub fn push(&mut self, name: String, value: Expr) {
-        self.variables.insert(name, value);
+#[cfg(test)]
+impl Drop for Frame {
+    fn drop(&mut self) {
+        LIVE.with(|live| live.set(live.get() - 1));
     }
 }
+
+/// Frames binding closures that capture them, such as the frame of a δ binding a λ. The
+/// closures and the frame keep each other alive, so reference counting alone never frees
+/// them.
+#[derive(Debug, Default)]
+pub struct Cycles {
+    frames: Vec<Weak<RefCell<Frame>>>,
+    /// The number of frames at which to look for unreachable ones next.
+    threshold: usize,
+}
+
+impl Cycles {
+    const MIN_THRESHOLD: usize = 64;
+
+    /// Keeps track of `frame` if one of its closures captures it.
+    pub fn track(&mut self, frame: &Env) {
+        let cyclic = frame
+            .borrow()
+            .variables
+            .values()
+            .flatten()
+            .any(|value| match value {
+                Expr::Closure(closure) => Rc::ptr_eq(&closure.env, frame),
+                _ => false,
+            });
+
+        if !cyclic {
+            return;
+        }
+
+        self.frames.push(Rc::downgrade(frame));
+
+        if self.frames.len() >= self.threshold {
+            self.collect();
+            self.threshold = (self.frames.len() * 2).max(Self::MIN_THRESHOLD);
+        }
+    }
+
+    /// Frees the tracked frames that only their own closures refer to, by dropping those
+    /// closures.
+    pub fn collect(&mut self) {
+        self.frames.retain(|frame| {
+            let frame = match frame.upgrade() {
+                Some(frame) => frame,
+                None => return false,
+            };
+
+            if !unreachable(&frame) {
+                return true;
+            }
+
+            // Taken out first, as dropping the closures may drop other frames.
+            let variables = std::mem::take(&mut frame.borrow_mut().variables);
+            drop(variables);
+
+            false
+        });
+    }
+}
+
+/// Whether nothing refers to `frame` but the closures it binds, which nothing refers to
+/// but the frame, besides the one reference held by the caller.
+fn unreachable(frame: &Env) -> bool {
+    let own = match frame.try_borrow() {
+        Ok(borrowed) => borrowed
+            .variables
+            .values()
+            .flatten()
+            .filter(|value| match value {
+                Expr::Closure(closure) => {
+                    Rc::ptr_eq(&closure.env, frame) && Rc::strong_count(closure) == 1
+                }
+                _ => false,
+            })
+            .count(),
+        Err(_) => return false,
+    };
+
+    Rc::strong_count(frame) == own + 1
+}
@@ -2,13 +2,57 @@ use std::fmt::Display;
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum Keyword {
-    Def,      // 2
-    Lambda,   // 2
-    Arrow,    // 1+
-    External, // 2
-    Id,       // 1
-    Ignore,   // 0
-    Nil,      // 0
+    Def,             // 2+
+    Lambda,          // 2+
+    Arrow,           // 1+
+    External,        // 1+
+    Id,              // 1
+    Ignore,          // 0
+    Nil,             // 0
+    Match,           // 1+
+    If,              // 2-3
+    Cond,            // 0+
+    And,             // 0+
+    Or,              // 0+
+    Quote,           // 1
+    Quasiquote,      // 1
+    Unquote,         // 1
+    UnquoteSplicing, // 1
+    Defmacro,        // 3
+    Macroexpand,     // 1
+    Macroexpand1,    // 1
+    Set,             // 2
+    Begin,           // 0+
+    Let,             // 2+
+    LetStar,         // 2+
+    Letrec,          // 2+
+}
+
+impl Keyword {
+    /// The reader syntax standing for a one-operand form of the keyword, if any.
+    pub fn prefix(&self) -> Option<&'static str> {
+        match self {
+            Keyword::Quote => Some("'"),
+            Keyword::Quasiquote => Some("`"),
+            Keyword::Unquote => Some(","),
+            Keyword::UnquoteSplicing => Some(",@"),
+            _ => None,
+        }
+    }
+
+    /// Whether the form ends with a body of any number of forms. Applying the result of
+    /// such a form is not flattened into it, since `((λ x a) b)` would read as `(λ x a b)`.
+    pub fn has_body(&self) -> bool {
+        matches!(
+            self,
+            Keyword::Lambda
+                | Keyword::Def
+                | Keyword::Begin
+                | Keyword::Let
+                | Keyword::LetStar
+                | Keyword::Letrec
+        )
+    }
 }
 
 impl Display for Keyword {
@@ -21,6 +65,23 @@ impl Display for Keyword {
             Keyword::Id => f.write_str("ι"),
             Keyword::Ignore => f.write_str("_"),
             Keyword::Nil => f.write_str("Ω"),
+            Keyword::Match => f.write_str("match"),
+            Keyword::If => f.write_str("if"),
+            Keyword::Cond => f.write_str("cond"),
+            Keyword::And => f.write_str("and"),
+            Keyword::Or => f.write_str("or"),
+            Keyword::Quote => f.write_str("quote"),
+            Keyword::Quasiquote => f.write_str("quasiquote"),
+            Keyword::Unquote => f.write_str("unquote"),
+            Keyword::UnquoteSplicing => f.write_str("unquote-splicing"),
+            Keyword::Defmacro => f.write_str("defmacro"),
+            Keyword::Macroexpand => f.write_str("macroexpand"),
+            Keyword::Macroexpand1 => f.write_str("macroexpand-1"),
+            Keyword::Set => f.write_str("set!"),
+            Keyword::Begin => f.write_str("begin"),
+            Keyword::Let => f.write_str("let"),
+            Keyword::LetStar => f.write_str("let*"),
+            Keyword::Letrec => f.write_str("letrec"),
         }
     }
 }
@@ -1,6 +1,11 @@
 use std::vec;
 
-use crate::{keywords::Keyword, literal::Literal, position::Position};
+use crate::{
+    error::EvalError,
+    keywords::Keyword,
+    literal::Literal,
+    position::{Position, Span},
+};
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum Lexem {
@@ -8,15 +13,27 @@ pub enum Lexem {
     Literal(Literal),
     ParenthesisOpen,
     ParenthesisClose,
+    BraceOpen,
+    BraceClose,
+    BracketOpen,
+    BracketClose,
+    /// Reader syntax standing for a one-operand form, e.g. `'x` for `(quote x)`.
+    Prefix(Keyword),
     Identifier(String),
 }
 
-pub type Token = (Lexem, Position);
+pub type Token = (Lexem, Span);
 
 pub struct Lexer {
     current: String,
+    start: Position,
     position: Position,
     in_string: bool,
+    /// Whether the previous character was a backslash inside a string.
+    escaped: bool,
+    /// Parentheses, braces and brackets opened but not yet closed, negative when there are
+    /// extra closing ones.
+    depth: isize,
     pub tokens: Vec<Token>,
 }
 
@@ -24,14 +41,27 @@ impl Lexer {
     pub fn new() -> Self {
         Self {
             current: "".to_string(),
+            start: Position::new(0, 0),
             position: Position::new(0, 0),
             tokens: vec![],
             in_string: false,
+            escaped: false,
+            depth: 0,
         }
     }
 
+    fn span(&self) -> Span {
+        Span::new(self.start, self.position)
+    }
+
+    /// Whether the current token is `#\\`, which the next character completes, even if it
+    /// is a parenthesis, a brace, a bracket or a quote.
+    fn in_char(&self) -> bool {
+        !self.in_string && self.current == "#\\"
+    }
+
     fn sublex_keyword(&self) -> Option<Token> {
-        let position = self.position.clone();
+        let position = self.span();
 
         match self.current.as_str() {
             "δ" | "def" => Some((Lexem::Keyword(Keyword::Def), position)),
@@ -41,6 +71,23 @@ impl Lexer {
             "ι" | "id" => Some((Lexem::Keyword(Keyword::Id), position)),
             "_" => Some((Lexem::Keyword(Keyword::Ignore), position)),
             "Ω" | "nih" => Some((Lexem::Keyword(Keyword::Nil), position)),
+            "match" => Some((Lexem::Keyword(Keyword::Match), position)),
+            "if" => Some((Lexem::Keyword(Keyword::If), position)),
+            "cond" => Some((Lexem::Keyword(Keyword::Cond), position)),
+            "and" => Some((Lexem::Keyword(Keyword::And), position)),
+            "or" => Some((Lexem::Keyword(Keyword::Or), position)),
+            "quote" => Some((Lexem::Keyword(Keyword::Quote), position)),
+            "quasiquote" => Some((Lexem::Keyword(Keyword::Quasiquote), position)),
+            "unquote" => Some((Lexem::Keyword(Keyword::Unquote), position)),
+            "unquote-splicing" => Some((Lexem::Keyword(Keyword::UnquoteSplicing), position)),
+            "defmacro" => Some((Lexem::Keyword(Keyword::Defmacro), position)),
+            "macroexpand" => Some((Lexem::Keyword(Keyword::Macroexpand), position)),
+            "macroexpand-1" => Some((Lexem::Keyword(Keyword::Macroexpand1), position)),
+            "set!" => Some((Lexem::Keyword(Keyword::Set), position)),
+            "begin" | "do" => Some((Lexem::Keyword(Keyword::Begin), position)),
+            "let" => Some((Lexem::Keyword(Keyword::Let), position)),
+            "let*" => Some((Lexem::Keyword(Keyword::LetStar), position)),
+            "letrec" => Some((Lexem::Keyword(Keyword::Letrec), position)),
             _ => None,
         }
     }
@@ -48,22 +95,21 @@ impl Lexer {
     fn sublex_literal(&self) -> Option<Token> {
         Literal::num(&self.current)
             .or(Literal::string(&self.current))
+            .or(Literal::char(&self.current))
+            .or(Literal::bool(&self.current))
             .or(Literal::nil(&self.current))
-            .map(|l| (Lexem::Literal(l), self.position.clone()))
+            .map(|l| (Lexem::Literal(l), self.span()))
     }
 
     fn sublex_identifier(&self) -> Option<Token> {
         if self.current.is_empty() || self.current.contains("\"") {
             None
         } else {
-            Some((
-                Lexem::Identifier(self.current.clone()),
-                self.position.clone(),
-            ))
+            Some((Lexem::Identifier(self.current.clone()), self.span()))
         }
     }
 
-    fn push(&mut self, token: Option<Lexem>) -> Result<(), String> {
+    fn push(&mut self, token: Option<Lexem>) -> Result<(), EvalError> {
         let result = match self
             .sublex_keyword()
             .or_else(|| self.sublex_literal())
@@ -72,47 +118,104 @@ impl Lexer {
         {
             Some(_) => Ok(()),
             None if self.current.is_empty() => Ok(()),
-            None => Err(format!("Cannot parse {}", self.current)),
+            None => Err(EvalError::parse(
+                format!("Cannot parse {}", self.current),
+                Some(self.span()),
+            )),
         };
 
         self.current = "".to_string();
 
         if let Some(t) = token {
-            if !self.current.is_empty() {
-                self.position.next_col();
-            }
+            let start = self.position;
 
-            self.tokens.push((t, self.position.clone()))
+            self.tokens.push((
+                t,
+                Span::new(start, Position::new(start.row(), start.col() + 1)),
+            ))
         }
 
         result
     }
 
-    pub fn lex(&mut self, source: &String) -> Result<(), String> {
-        for c in source.chars() {
-            self.position.next_col();
+    pub fn lex(&mut self, source: &str) -> Result<(), EvalError> {
+        let mut chars = source.chars().peekable();
 
+        while let Some(c) = chars.next() {
             if c.is_whitespace() && !self.in_string {
-                self.push(None).unwrap();
-
-                if c == '\n' {
-                    self.position.next_row();
-                } else {
-                    self.position.next_col();
-                }
+                self.push(None)?;
             } else {
                 match c {
-                    '(' => self.push(Some(Lexem::ParenthesisOpen)).unwrap(),
-                    ')' => self.push(Some(Lexem::ParenthesisClose)).unwrap(),
+                    '(' if !self.in_string && !self.in_char() => {
+                        self.depth += 1;
+                        self.push(Some(Lexem::ParenthesisOpen))?
+                    }
+                    ')' if !self.in_string && !self.in_char() => {
+                        self.depth -= 1;
+                        self.push(Some(Lexem::ParenthesisClose))?
+                    }
+                    '{' if !self.in_string && !self.in_char() => {
+                        self.depth += 1;
+                        self.push(Some(Lexem::BraceOpen))?
+                    }
+                    '}' if !self.in_string && !self.in_char() => {
+                        self.depth -= 1;
+                        self.push(Some(Lexem::BraceClose))?
+                    }
+                    '[' if !self.in_string && !self.in_char() => {
+                        self.depth += 1;
+                        self.push(Some(Lexem::BracketOpen))?
+                    }
+                    ']' if !self.in_string && !self.in_char() => {
+                        self.depth -= 1;
+                        self.push(Some(Lexem::BracketClose))?
+                    }
+                    '\'' if !self.in_string && self.current.is_empty() => {
+                        self.push(Some(Lexem::Prefix(Keyword::Quote)))?
+                    }
+                    '`' if !self.in_string && self.current.is_empty() => {
+                        self.push(Some(Lexem::Prefix(Keyword::Quasiquote)))?
+                    }
+                    ',' if !self.in_string
+                        && self.current.is_empty()
+                        && chars.peek() == Some(&'@') =>
+                    {
+                        chars.next();
+                        self.push(Some(Lexem::Prefix(Keyword::UnquoteSplicing)))?;
+
+                        // `,@` spans two columns.
+                        let start = self.position;
+                        self.position.next_col();
+                        if let Some((_, span)) = self.tokens.last_mut() {
+                            *span = Span::new(start, Position::new(start.row(), start.col() + 2));
+                        }
+                    }
+                    ',' if !self.in_string && self.current.is_empty() => {
+                        self.push(Some(Lexem::Prefix(Keyword::Unquote)))?
+                    }
                     c => {
-                        if c == '"' {
+                        if self.escaped {
+                            self.escaped = false;
+                        } else if c == '\\' && self.in_string {
+                            self.escaped = true;
+                        } else if c == '"' && !self.in_char() {
                             self.in_string = !self.in_string;
                         }
 
+                        if self.current.is_empty() {
+                            self.start = self.position;
+                        }
+
                         self.current.push(c)
                     }
                 };
             }
+
+            if c == '\n' {
+                self.position.next_row();
+            } else {
+                self.position.next_col();
+            }
         }
 
         self.push(None)?;
@@ -120,7 +223,17 @@ impl Lexer {
         Ok(())
     }
 
+    pub fn in_string(&self) -> bool {
+        self.in_string
+    }
+
+    /// Whether the source lexed so far leaves a string, a parenthesis, a brace or a bracket
+    /// open.
+    pub fn is_open(&self) -> bool {
+        self.in_string || self.depth > 0
+    }
+
     pub fn lexems(&self) -> Vec<&Token> {
-        self.tokens.iter().map(|t| t).collect::<Vec<&Token>>()
+        self.tokens.iter().collect::<Vec<&Token>>()
     }
 }
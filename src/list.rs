@@ -0,0 +1,117 @@
+use std::{fmt::Display, rc::Rc};
+
+use crate::parser::Expr;
+
+/// An immutable singly linked list, sharing its tails.
+#[derive(Debug, Clone, Default)]
+pub struct List(Option<Rc<(Expr, List)>>);
+
+impl List {
+    pub fn nil() -> Self {
+        List(None)
+    }
+
+    pub fn cons(head: Expr, tail: List) -> Self {
+        List(Some(Rc::new((head, tail))))
+    }
+
+    pub fn head(&self) -> Option<&Expr> {
+        self.0.as_ref().map(|cell| &cell.0)
+    }
+
+    pub fn tail(&self) -> Option<&List> {
+        self.0.as_ref().map(|cell| &cell.1)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_none()
+    }
+
+    pub fn iter(&self) -> Iter<'_> {
+        Iter(self)
+    }
+}
+
+impl FromIterator<Expr> for List {
+    fn from_iter<T: IntoIterator<Item = Expr>>(iter: T) -> Self {
+        iter.into_iter()
+            .collect::<Vec<Expr>>()
+            .into_iter()
+            .rev()
+            .fold(List::nil(), |tail, head| List::cons(head, tail))
+    }
+}
+
+pub struct Iter<'a>(&'a List);
+
+impl<'a> Iterator for Iter<'a> {
+    type Item = &'a Expr;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (head, tail) = self.0 .0.as_deref()?;
+        self.0 = tail;
+
+        Some(head)
+    }
+}
+
+impl Drop for List {
+    // Dropping a long list recursively would overflow the stack.
+    fn drop(&mut self) {
+        if self.0.is_some() {
+            drop_nested(Expr::List(List(self.0.take())));
+        }
+    }
+}
+
+/// Drops `value` without recursing into the lists and cells nothing else refers to, which
+/// may be nested deeper than the stack allows. Their items go to a work stack instead, and
+/// what is left of them is dropped empty.
+pub fn drop_nested(value: Expr) {
+    let mut stack = vec![value];
+
+    while let Some(value) = stack.pop() {
+        match value {
+            Expr::List(mut list) => {
+                let mut next = list.0.take();
+
+                while let Some(cell) = next {
+                    next = match Rc::try_unwrap(cell) {
+                        Ok((head, mut tail)) => {
+                            if matches!(head, Expr::List(_) | Expr::Cell(_)) {
+                                stack.push(head);
+                            }
+
+                            tail.0.take()
+                        }
+                        Err(_) => break,
+                    };
+                }
+            }
+            Expr::Cell(mut cell) => stack.extend(cell.take()),
+            _ => {}
+        }
+    }
+}
+
+impl Display for List {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // Lists such as `(quote x)` are printed back in reader syntax.
+        if let (Some(Expr::Keyword(keyword)), Some(tail)) = (self.head(), self.tail()) {
+            if let (Some(prefix), Some(operand), Some(true)) = (
+                keyword.prefix(),
+                tail.head(),
+                tail.tail().map(List::is_empty),
+            ) {
+                return f.write_fmt(format_args!("{}{}", prefix, operand));
+            }
+        }
+
+        let items = self
+            .iter()
+            .map(|item| format!("{}", item))
+            .collect::<Vec<String>>();
+
+        f.write_fmt(format_args!("({})", items.join(" ")))
+    }
+}
@@ -1,63 +1,135 @@
 use std::fmt::Display;
 
+use crate::{number::Number, symbol::Symbol};
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum Literal {
-    Num(f64),
+    Num(Number),
     String(String),
+    Char(char),
+    Symbol(Symbol),
+    Bool(bool),
     Nil,
 }
 
+/// Reads the `{...}` of a `\u{...}` escape: one to six hexadecimal digits.
+fn unicode(chars: &mut std::str::Chars) -> Option<char> {
+    if chars.next()? != '{' {
+        return None;
+    }
+
+    let mut digits = String::new();
+    loop {
+        match chars.next()? {
+            '}' => break,
+            c => digits.push(c),
+        }
+    }
+
+    if digits.is_empty() || digits.len() > 6 {
+        return None;
+    }
+
+    char::from_u32(u32::from_str_radix(&digits, 16).ok()?)
+}
+
+/// Characters written by name after `#\\`, such as `#\\space`.
+const CHAR_NAMES: [(&str, char); 5] = [
+    ("space", ' '),
+    ("newline", '\n'),
+    ("tab", '\t'),
+    ("return", '\r'),
+    ("nul", '\0'),
+];
+
+/// Escapes a string so that it reads back as itself.
+fn escape(string: &str) -> String {
+    string
+        .chars()
+        .map(|c| match c {
+            '\n' => "\\n".to_string(),
+            '\t' => "\\t".to_string(),
+            '\r' => "\\r".to_string(),
+            '\0' => "\\0".to_string(),
+            '"' => "\\\"".to_string(),
+            '\\' => "\\\\".to_string(),
+            c => c.to_string(),
+        })
+        .collect()
+}
+
 impl Display for Literal {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Literal::Num(num) => f.write_fmt(format_args!("{}", num)),
-            Literal::String(string) => f.write_fmt(format_args!("'{}'", string)),
+            Literal::String(string) => f.write_fmt(format_args!("\"{}\"", escape(string))),
+            Literal::Char(c) => match CHAR_NAMES.iter().find(|(_, named)| named == c) {
+                Some((name, _)) => f.write_fmt(format_args!("#\\{}", name)),
+                None => f.write_fmt(format_args!("#\\{}", c)),
+            },
+            Literal::Symbol(symbol) => f.write_fmt(format_args!("{}", symbol)),
+            Literal::Bool(true) => f.write_str("#t"),
+            Literal::Bool(false) => f.write_str("#f"),
             Literal::Nil => f.write_str("Φ"),
         }
     }
 }
 
 impl Literal {
-    pub fn num(s: &String) -> Option<Literal> {
-        match s.parse::<f64>() {
-            Ok(f) => Some(Literal::Num(f)),
-            Err(_) => None,
-        }
+    pub fn num(s: &str) -> Option<Literal> {
+        Number::parse(s).map(Literal::Num)
     }
 
-    pub fn string(s: &String) -> Option<Literal> {
-        if s.len() <= 1 {
-            return None;
-        }
-
-        let has_opening_quote = s.chars().next().unwrap() == '"';
-        let has_closing_quote = s.chars().last().unwrap() == '"';
+    /// Reads a string token, decoding the escapes `\n`, `\t`, `\r`, `\0`, `\"`, `\\` and
+    /// `\u{...}`.
+    pub fn string(s: &str) -> Option<Literal> {
+        let mut chars = s.strip_prefix('"')?.strip_suffix('"')?.chars();
+        let mut string = String::new();
 
-        if !(has_opening_quote && has_closing_quote) {
-            return None;
+        while let Some(c) = chars.next() {
+            match c {
+                '"' => return None,
+                '\\' => string.push(match chars.next()? {
+                    'n' => '\n',
+                    't' => '\t',
+                    'r' => '\r',
+                    '0' => '\0',
+                    '"' => '"',
+                    '\\' => '\\',
+                    'u' => unicode(&mut chars)?,
+                    _ => return None,
+                }),
+                c => string.push(c),
+            }
         }
 
-        let mut stream = s.chars().peekable().enumerate();
-
-        while let Some((i, c)) = stream.next() {
-            if c == '"' && !(i == 0 || i == s.len() - 1) {
-                return None;
-            }
+        Some(Literal::String(string))
+    }
 
-            if c == '\\' && i == s.len() - 1 {
-                return None;
-            }
+    /// Reads `#\\a`, or a character by name such as `#\\space`.
+    pub fn char(s: &str) -> Option<Literal> {
+        let name = s.strip_prefix("#\\")?;
+        let mut chars = name.chars();
 
-            if c == '\\' {
-                stream.next();
-            }
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => Some(Literal::Char(c)),
+            _ => CHAR_NAMES
+                .iter()
+                .find(|(named, _)| *named == name)
+                .map(|(_, c)| Literal::Char(*c)),
         }
+    }
 
-        return Some(Literal::String(s[1..=(s.len() - 2)].to_string()));
+    pub fn bool(s: &str) -> Option<Literal> {
+        match s {
+            "#t" => Some(Literal::Bool(true)),
+            "#f" => Some(Literal::Bool(false)),
+            _ => None,
+        }
     }
 
-    pub fn nil(s: &String) -> Option<Literal> {
-        if s.as_str() == "Φ" || s.as_str() == "nil" {
+    pub fn nil(s: &str) -> Option<Literal> {
+        if s == "Φ" || s == "nil" {
             Some(Literal::Nil)
         } else {
             None
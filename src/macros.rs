@@ -0,0 +1,98 @@
+use std::fmt::Display;
+
+use crate::{closure::REST_SEPARATOR, error::EvalError, keywords::Keyword, parser::Expr};
+
+/// A macro defined by `(defmacro name (a b) template)`. Expanding `(name x y)` evaluates
+/// the template with `a` and `b` bound to the forms `x` and `y` as data, and the code the
+/// resulting datum reads as replaces the call.
+#[derive(Debug, Clone)]
+pub struct Macro {
+    pub name: String,
+    pub parameters: Vec<String>,
+    /// Bound to the list of the forms following the parameters, `(a b . rest)`.
+    pub rest: Option<String>,
+    pub template: Expr,
+}
+
+impl Macro {
+    /// Reads the operands of `defmacro`: a name, the parameters and the template. The
+    /// parameters are a variable, a list of variables, possibly ending with a rest
+    /// parameter, or `_` for none.
+    pub fn new(operands: &[Expr]) -> Result<Self, EvalError> {
+        let (name, parameters, template) = match operands {
+            [Expr::Var { name, .. }, parameters, template] => (name, parameters, template),
+            [name, _, _] => {
+                return Err(EvalError::failure(format!(
+                    "Invalid defmacro name: {}",
+                    name
+                )))
+            }
+            _ => {
+                return Err(EvalError::arity(
+                    format!("{}", Keyword::Defmacro),
+                    "3".to_string(),
+                    operands.len(),
+                ))
+            }
+        };
+
+        let invalid = || EvalError::failure(format!("Invalid defmacro parameters: {}", parameters));
+        let name_of = |parameter: &Expr| match parameter {
+            Expr::Var { name, .. } => Ok(name.clone()),
+            _ => Err(invalid()),
+        };
+
+        let mut names = match parameters {
+            Expr::Keyword(Keyword::Ignore) => vec![],
+            Expr::Var { .. } => vec![name_of(parameters)?],
+            Expr::Expr {
+                operator, operands, ..
+            } => std::iter::once(operator.as_ref())
+                .chain(operands)
+                .map(name_of)
+                .collect::<Result<Vec<String>, EvalError>>()?,
+            _ => return Err(invalid()),
+        };
+
+        let rest = match names.iter().position(|name| name == REST_SEPARATOR) {
+            Some(i) if i + 2 == names.len() && names[i + 1] != REST_SEPARATOR => {
+                let rest = names.pop();
+                names.pop();
+
+                rest
+            }
+            Some(_) => return Err(invalid()),
+            None => None,
+        };
+
+        Ok(Self {
+            name: name.clone(),
+            parameters: names,
+            rest,
+            template: template.clone(),
+        })
+    }
+
+    /// The names the template is evaluated with.
+    pub fn names(&self) -> Vec<String> {
+        self.parameters.iter().chain(&self.rest).cloned().collect()
+    }
+}
+
+impl Display for Macro {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut parameters = self.parameters.clone();
+        if let Some(rest) = &self.rest {
+            parameters.push(REST_SEPARATOR.to_string());
+            parameters.push(rest.clone());
+        }
+
+        f.write_fmt(format_args!(
+            "({} {} ({}) {})",
+            Keyword::Defmacro,
+            self.name,
+            parameters.join(" "),
+            self.template
+        ))
+    }
+}
@@ -1,22 +1,68 @@
-use std::error::Error;
+use std::{
+    error::Error,
+    io::{self, IsTerminal},
+    process, thread,
+};
 
-use evaluator::Runtime;
-use lexer::Lexer;
-use parser::{run_parser, Expr};
-
-use crate::literal::Literal;
+use script::{Input, Options};
 
+mod cell;
+mod closure;
+mod error;
 mod evaluator;
+mod external;
 mod frame;
 mod keywords;
 mod lexer;
+mod list;
 mod literal;
+mod macros;
+mod map;
+mod number;
 mod parser;
+mod pattern;
 mod position;
+mod primitive;
 mod repl;
+mod script;
+mod symbol;
+mod vector;
+
+const STACK_SIZE: usize = 256 * 1024 * 1024;
 
 fn main() -> Result<(), Box<dyn Error>> {
-    repl::run();
+    let mut options = match Options::parse(std::env::args().skip(1)) {
+        Ok(options) => options,
+        Err(err) => {
+            eprintln!("{}\n{}", err, script::USAGE);
+            process::exit(2);
+        }
+    };
+
+    if options.help {
+        println!("{}", script::USAGE);
+        return Ok(());
+    }
+
+    if options.input == Input::Repl && !io::stdin().is_terminal() {
+        options.input = Input::Stdin;
+    }
+
+    // Nested evaluations use the native stack, so the interpreter runs on a thread with
+    // room for `evaluator::MAX_DEPTH` of them.
+    let interpreter = thread::Builder::new()
+        .stack_size(STACK_SIZE)
+        .spawn(move || match options.input {
+            Input::Repl => {
+                repl::run();
+                0
+            }
+            _ => script::run(options),
+        })?;
 
-    Ok(())
+    match interpreter.join() {
+        Ok(0) => Ok(()),
+        Ok(code) => process::exit(code),
+        Err(_) => process::exit(101),
+    }
 }
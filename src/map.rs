@@ -0,0 +1,120 @@
+use std::{cmp::Ordering, collections::BTreeMap, fmt::Display, rc::Rc};
+
+use crate::{literal::Literal, parser::Expr};
+
+/// A map key: any literal but NaN, ordered so that maps print in a stable order.
+#[derive(Debug, Clone)]
+pub struct Key(Literal);
+
+impl Key {
+    pub fn new(expr: &Expr) -> Option<Self> {
+        match expr {
+            Expr::Literal(Literal::Num(num)) if num.compare(num).is_none() => None,
+            Expr::Literal(literal) => Some(Key(literal.clone())),
+            _ => None,
+        }
+    }
+
+    pub fn to_expr(&self) -> Expr {
+        Expr::Literal(self.0.clone())
+    }
+
+    fn rank(&self) -> u8 {
+        match self.0 {
+            Literal::Nil => 0,
+            Literal::Bool(_) => 1,
+            Literal::Num(_) => 2,
+            Literal::Char(_) => 3,
+            Literal::String(_) => 4,
+            Literal::Symbol(_) => 5,
+        }
+    }
+}
+
+impl Ord for Key {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (&self.0, &other.0) {
+            (Literal::Bool(a), Literal::Bool(b)) => a.cmp(b),
+            // Numbers of the same value but different types, like 1 and 1.0, are different
+            // keys.
+            (Literal::Num(a), Literal::Num(b)) => a
+                .compare(b)
+                .unwrap_or(Ordering::Equal)
+                .then(a.level().cmp(&b.level())),
+            (Literal::Char(a), Literal::Char(b)) => a.cmp(b),
+            (Literal::String(a), Literal::String(b)) => a.cmp(b),
+            (Literal::Symbol(a), Literal::Symbol(b)) => a.name().cmp(b.name()),
+            _ => self.rank().cmp(&other.rank()),
+        }
+    }
+}
+
+impl PartialOrd for Key {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Key {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Key {}
+
+/// An immutable map. Updates copy the entries the map shares with others, leaving
+/// those unchanged.
+#[derive(Debug, Clone, Default)]
+pub struct Map(Rc<BTreeMap<Key, Expr>>);
+
+impl Map {
+    pub fn new() -> Self {
+        Map::default()
+    }
+
+    pub fn get(&self, key: &Key) -> Option<&Expr> {
+        self.0.get(key)
+    }
+
+    pub fn contains(&self, key: &Key) -> bool {
+        self.0.contains_key(key)
+    }
+
+    pub fn insert(&self, key: Key, value: Expr) -> Self {
+        let mut map = self.clone();
+        Rc::make_mut(&mut map.0).insert(key, value);
+
+        map
+    }
+
+    pub fn remove(&self, key: &Key) -> Self {
+        if !self.contains(key) {
+            return self.clone();
+        }
+
+        let mut map = self.clone();
+        Rc::make_mut(&mut map.0).remove(key);
+
+        map
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&Key, &Expr)> {
+        self.0.iter()
+    }
+}
+
+impl Display for Map {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let entries = self
+            .iter()
+            .map(|(key, value)| format!("{} {}", key.to_expr(), value))
+            .collect::<Vec<String>>();
+
+        f.write_fmt(format_args!("{{{}}}", entries.join(" ")))
+    }
+}
@@ -0,0 +1,270 @@
+use std::{cmp::Ordering, fmt::Display};
+
+use num_bigint::BigInt;
+use num_integer::Integer;
+use num_rational::BigRational;
+use num_traits::{Pow, Signed, ToPrimitive, Zero};
+
+/// A number of the tower integer < big integer < rational < float. Exact results are
+/// kept in the lowest type that holds them, and arithmetic on two numbers is carried
+/// out in the higher of their types.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Number {
+    Int(i64),
+    /// An integer outside of the range of `Int`.
+    Big(BigInt),
+    /// A fraction in lowest terms whose denominator is not 1.
+    Ratio(BigRational),
+    Float(f64),
+}
+
+impl Number {
+    /// Reads `42`, `-0xff`, `3/4` or `1.5e3`.
+    pub fn parse(s: &str) -> Option<Number> {
+        let (negative, digits) = match s.strip_prefix('-') {
+            Some(digits) => (true, digits),
+            None => (false, s.strip_prefix('+').unwrap_or(s)),
+        };
+        let signed = |n: BigInt| if negative { -n } else { n };
+
+        // Names such as `inf`, `e` or `.` are variables rather than numbers.
+        let digit = |s: &str| s.starts_with(|c: char| c.is_ascii_digit());
+        if !(digit(digits) || digits.strip_prefix('.').is_some_and(digit)) {
+            return None;
+        }
+
+        if let Some(hex) = digits
+            .strip_prefix("0x")
+            .or_else(|| digits.strip_prefix("0X"))
+        {
+            return BigInt::parse_bytes(hex.as_bytes(), 16).map(|n| Number::integer(signed(n)));
+        }
+
+        if let Some((numerator, denominator)) = digits.split_once('/') {
+            let numerator = BigInt::parse_bytes(numerator.as_bytes(), 10)?;
+            let denominator = BigInt::parse_bytes(denominator.as_bytes(), 10)?;
+
+            if denominator.is_zero() || denominator.is_negative() {
+                return None;
+            }
+
+            return Some(Number::rational(BigRational::new(
+                signed(numerator),
+                denominator,
+            )));
+        }
+
+        match BigInt::parse_bytes(digits.as_bytes(), 10) {
+            Some(n) => Some(Number::integer(signed(n))),
+            None => s.parse::<f64>().ok().map(Number::Float),
+        }
+    }
+
+    pub fn integer(n: BigInt) -> Number {
+        match n.to_i64() {
+            Some(n) => Number::Int(n),
+            None => Number::Big(n),
+        }
+    }
+
+    pub fn rational(n: BigRational) -> Number {
+        if n.is_integer() {
+            Number::integer(n.to_integer())
+        } else {
+            Number::Ratio(n)
+        }
+    }
+
+    /// The position of the type in the tower.
+    pub fn level(&self) -> u8 {
+        match self {
+            Number::Int(_) => 0,
+            Number::Big(_) => 1,
+            Number::Ratio(_) => 2,
+            Number::Float(_) => 3,
+        }
+    }
+
+    fn to_big(&self) -> BigInt {
+        match self {
+            Number::Int(n) => BigInt::from(*n),
+            Number::Big(n) => n.clone(),
+            Number::Ratio(n) => n.to_integer(),
+            Number::Float(n) => BigInt::from(*n as i64),
+        }
+    }
+
+    fn to_ratio(&self) -> BigRational {
+        match self {
+            Number::Ratio(n) => n.clone(),
+            n => BigRational::from_integer(n.to_big()),
+        }
+    }
+
+    pub fn to_f64(&self) -> f64 {
+        match self {
+            Number::Int(n) => *n as f64,
+            Number::Big(n) => n.to_f64().unwrap_or(f64::NAN),
+            Number::Ratio(n) => n.to_f64().unwrap_or(f64::NAN),
+            Number::Float(n) => *n,
+        }
+    }
+
+    pub fn is_zero(&self) -> bool {
+        match self {
+            Number::Int(n) => *n == 0,
+            Number::Big(n) => n.is_zero(),
+            Number::Ratio(n) => n.is_zero(),
+            Number::Float(n) => *n == 0.0,
+        }
+    }
+
+    /// Applies an operation at the higher level of the two numbers: on `i64`s, falling back
+    /// to big integers when they overflow, then on big integers, rationals or floats.
+    fn combine(
+        &self,
+        other: &Number,
+        int: fn(i64, i64) -> Option<i64>,
+        big: fn(BigInt, BigInt) -> BigInt,
+        ratio: fn(BigRational, BigRational) -> BigRational,
+        float: fn(f64, f64) -> f64,
+    ) -> Number {
+        match (self, other, self.level().max(other.level())) {
+            (Number::Int(a), Number::Int(b), _) => match int(*a, *b) {
+                Some(n) => Number::Int(n),
+                None => Number::integer(big(BigInt::from(*a), BigInt::from(*b))),
+            },
+            (a, b, 1) => Number::integer(big(a.to_big(), b.to_big())),
+            (a, b, 2) => Number::rational(ratio(a.to_ratio(), b.to_ratio())),
+            (a, b, _) => Number::Float(float(a.to_f64(), b.to_f64())),
+        }
+    }
+
+    pub fn add(&self, other: &Number) -> Number {
+        self.combine(
+            other,
+            i64::checked_add,
+            |a, b| a + b,
+            |a, b| a + b,
+            |a, b| a + b,
+        )
+    }
+
+    pub fn sub(&self, other: &Number) -> Number {
+        self.combine(
+            other,
+            i64::checked_sub,
+            |a, b| a - b,
+            |a, b| a - b,
+            |a, b| a - b,
+        )
+    }
+
+    pub fn mul(&self, other: &Number) -> Number {
+        self.combine(
+            other,
+            i64::checked_mul,
+            |a, b| a * b,
+            |a, b| a * b,
+            |a, b| a * b,
+        )
+    }
+
+    /// Divides exactly, so integers divide into rationals. The divisor must not be zero.
+    pub fn div(&self, other: &Number) -> Number {
+        match (self, other) {
+            (Number::Float(_), _) | (_, Number::Float(_)) => {
+                Number::Float(self.to_f64() / other.to_f64())
+            }
+            (a, b) => Number::rational(a.to_ratio() / b.to_ratio()),
+        }
+    }
+
+    /// The remainder of Euclidean division, which is never negative. The divisor must not
+    /// be zero.
+    pub fn rem_euclid(&self, other: &Number) -> Number {
+        self.combine(
+            other,
+            i64::checked_rem_euclid,
+            |a, b| a.mod_floor(&b.abs()),
+            |a, b| &a - b.abs() * (&a / b.abs()).floor(),
+            f64::rem_euclid,
+        )
+    }
+
+    /// Compares numbers by value, unlike `==` which also tells `1` and `1.0` apart. Only
+    /// NaN is unordered.
+    pub fn compare(&self, other: &Number) -> Option<Ordering> {
+        match (self, other, self.level().max(other.level())) {
+            (Number::Int(a), Number::Int(b), _) => a.partial_cmp(b),
+            (a, b, 1) => a.to_big().partial_cmp(&b.to_big()),
+            (a, b, 2) => a.to_ratio().partial_cmp(&b.to_ratio()),
+            (a, b, _) => a.to_f64().partial_cmp(&b.to_f64()),
+        }
+    }
+
+    pub fn neg(&self) -> Number {
+        Number::Int(0).sub(self)
+    }
+
+    pub fn abs(&self) -> Number {
+        match self.compare(&Number::Int(0)) {
+            Some(Ordering::Less) => self.neg(),
+            _ => self.clone(),
+        }
+    }
+
+    /// Raises to an integer power exactly, and to any other power in floats.
+    pub fn pow(&self, exponent: &Number) -> Number {
+        match (self, exponent) {
+            (Number::Float(_), _) | (_, Number::Float(_)) => {
+                Number::Float(self.to_f64().powf(exponent.to_f64()))
+            }
+            (base, Number::Int(exponent)) if !(base.is_zero() && *exponent < 0) => {
+                match i32::try_from(*exponent) {
+                    Ok(exponent) => Number::rational(base.to_ratio().pow(exponent)),
+                    Err(_) => Number::Float(base.to_f64().powf(*exponent as f64)),
+                }
+            }
+            (base, exponent) => Number::Float(base.to_f64().powf(exponent.to_f64())),
+        }
+    }
+
+    /// The square root, exact when the number is the square of an exact number.
+    pub fn sqrt(&self) -> Number {
+        let exact = |n: &BigInt| Some(n.sqrt()).filter(|root| root * root == *n);
+
+        match self {
+            Number::Float(_) => {}
+            n if n.compare(&Number::Int(0)) == Some(Ordering::Less) => {}
+            n => {
+                let n = n.to_ratio();
+                if let (Some(numerator), Some(denominator)) = (exact(n.numer()), exact(n.denom())) {
+                    return Number::rational(BigRational::new(numerator, denominator));
+                }
+            }
+        }
+
+        Number::Float(self.to_f64().sqrt())
+    }
+
+    pub fn floor(&self) -> Number {
+        match self {
+            Number::Ratio(n) => Number::integer(n.floor().to_integer()),
+            Number::Float(n) => Number::Float(n.floor()),
+            n => n.clone(),
+        }
+    }
+}
+
+impl Display for Number {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Number::Int(n) => f.write_fmt(format_args!("{}", n)),
+            Number::Big(n) => f.write_fmt(format_args!("{}", n)),
+            Number::Ratio(n) => f.write_fmt(format_args!("{}/{}", n.numer(), n.denom())),
+            // Floats keep their decimal point, so `2.0` is not mistaken for `2`.
+            Number::Float(n) => f.write_fmt(format_args!("{:?}", n)),
+        }
+    }
+}
@@ -1,37 +1,84 @@
-use std::fmt::Display;
+use std::{fmt::Display, rc::Rc};
 
 use crate::{
+    cell::Cell,
+    closure::Closure,
+    error::EvalError,
     keywords::Keyword,
     lexer::{Lexem, Lexer, Token},
+    list::List,
     literal::Literal,
+    map::{Key, Map},
+    position::Span,
+    primitive::Primitive,
+    symbol::Symbol,
+    vector::Vector,
 };
 
+#[allow(clippy::enum_variant_names)]
 #[derive(Debug, Clone)]
 pub enum Expr {
     Expr {
         operator: Box<Expr>,
         operands: Vec<Expr>,
+        span: Option<Span>,
     },
     Var {
         name: String,
+        span: Option<Span>,
     },
     Literal(Literal),
     Keyword(Keyword),
+    Primitive(Primitive),
+    Closure(Rc<Closure>),
+    List(List),
+    Map(Map),
+    Vector(Vector),
+    Cell(Cell),
 }
 
 impl Display for Expr {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Expr::Expr { operator, operands } => {
+            Expr::Expr {
+                operator, operands, ..
+            } => {
+                // Forms such as `(quote x)` are printed back in reader syntax.
+                if let (Expr::Keyword(keyword), [operand]) = (operator.as_ref(), &operands[..]) {
+                    if let Some(prefix) = keyword.prefix() {
+                        return f.write_fmt(format_args!("{}{}", prefix, operand));
+                    }
+                }
+
+                if let Expr::Primitive(primitive @ (Primitive::HashMap | Primitive::Vector)) =
+                    operator.as_ref()
+                {
+                    let items = operands
+                        .iter()
+                        .map(|operand| format!("{}", operand))
+                        .collect::<Vec<String>>();
+
+                    return match primitive {
+                        Primitive::HashMap => f.write_fmt(format_args!("{{{}}}", items.join(" "))),
+                        _ => f.write_fmt(format_args!("[{}]", items.join(" "))),
+                    };
+                }
+
                 let fmt_operands = operands
                     .iter()
                     .fold("".to_string(), |u, a| format!("{} {}", u, a));
 
                 f.write_fmt(format_args!("({}{})", operator, fmt_operands))
             }
-            Expr::Var { name } => f.write_fmt(format_args!("{}", name)),
+            Expr::Var { name, .. } => f.write_fmt(format_args!("{}", name)),
             Expr::Literal(lit) => f.write_fmt(format_args!("{}", lit)),
             Expr::Keyword(keyword) => f.write_fmt(format_args!("{}", keyword)),
+            Expr::Primitive(primitive) => f.write_fmt(format_args!("{}", primitive)),
+            Expr::Closure(closure) => f.write_fmt(format_args!("{}", closure)),
+            Expr::List(list) => f.write_fmt(format_args!("{}", list)),
+            Expr::Map(map) => f.write_fmt(format_args!("{}", map)),
+            Expr::Vector(vector) => f.write_fmt(format_args!("{}", vector)),
+            Expr::Cell(cell) => f.write_fmt(format_args!("{}", cell)),
         }
     }
 }
@@ -42,9 +89,164 @@ pub struct ParseResult<T: Clone> {
     next_position: usize,
 }
 
-impl<T: Clone> ParseResult<T> {
-    pub fn expr(&self) -> &T {
-        &self.expr
+impl Expr {
+    /// The value of a quoted datum, in which identifiers are symbols.
+    pub fn quoted(&self) -> Expr {
+        match self {
+            Expr::Var { name, .. } => Expr::Literal(Literal::Symbol(Symbol::new(name))),
+            Expr::List(items) => Expr::List(items.iter().map(Expr::quoted).collect()),
+            datum => datum.clone(),
+        }
+    }
+
+    /// The datum a quoted value was read from, the inverse of `quoted`.
+    fn unquoted(&self) -> Expr {
+        match self {
+            Expr::Literal(Literal::Symbol(symbol)) => Expr::Var {
+                name: symbol.name().to_string(),
+                span: None,
+            },
+            Expr::List(items) => Expr::List(items.iter().map(Expr::unquoted).collect()),
+            value => value.clone(),
+        }
+    }
+
+    /// Turns data into the code it reads as, e.g. the list `(+ 1 2)` into an application.
+    /// Parts the parser reads as data, such as quoted operands and `match` patterns, are kept.
+    pub fn to_code(&self) -> Expr {
+        let items = match self {
+            Expr::List(items) if !items.is_empty() => items.iter().collect::<Vec<&Expr>>(),
+            expr => return expr.unquoted(),
+        };
+
+        let operator = items[0].to_code();
+        let operands = match &operator {
+            Expr::Keyword(Keyword::Quote | Keyword::Quasiquote) => {
+                items[1..].iter().map(|item| item.unquoted()).collect()
+            }
+            Expr::Keyword(Keyword::Match) => items[1..]
+                .iter()
+                .enumerate()
+                .map(|(i, item)| match item {
+                    Expr::List(clause) if i > 0 => Expr::List(
+                        clause
+                            .iter()
+                            .enumerate()
+                            .map(|(j, part)| {
+                                if j == 0 {
+                                    part.unquoted()
+                                } else {
+                                    part.to_code()
+                                }
+                            })
+                            .collect(),
+                    ),
+                    item => item.to_code(),
+                })
+                .collect(),
+            Expr::Keyword(Keyword::Cond) => items[1..]
+                .iter()
+                .map(|item| match item {
+                    Expr::List(clause) => Expr::List(clause.iter().map(Expr::to_code).collect()),
+                    item => item.to_code(),
+                })
+                .collect(),
+            Expr::Keyword(Keyword::Let | Keyword::LetStar | Keyword::Letrec) => items[1..]
+                .iter()
+                .enumerate()
+                .map(|(i, item)| match item {
+                    Expr::List(bindings) if i == 0 => Expr::List(
+                        bindings
+                            .iter()
+                            .map(|binding| match binding {
+                                Expr::List(parts) => {
+                                    Expr::List(parts.iter().map(Expr::to_code).collect())
+                                }
+                                binding => binding.to_code(),
+                            })
+                            .collect(),
+                    ),
+                    item => item.to_code(),
+                })
+                .collect(),
+            _ => items[1..].iter().map(|item| item.to_code()).collect(),
+        };
+
+        Expr::Expr {
+            operator: Box::new(operator),
+            operands,
+            span: None,
+        }
+    }
+
+    /// Turns code back into data, the inverse of `to_code`.
+    pub fn to_datum(&self) -> Expr {
+        let (operator, operands) = match self {
+            Expr::Expr {
+                operator, operands, ..
+            } => (operator.as_ref(), operands),
+            expr => return expr.clone(),
+        };
+
+        let operands = operands
+            .iter()
+            .enumerate()
+            .map(|(i, operand)| match (operator, operand) {
+                (Expr::Keyword(Keyword::Quote | Keyword::Quasiquote), operand) => operand.clone(),
+                (Expr::Keyword(Keyword::Match), Expr::List(clause)) if i > 0 => Expr::List(
+                    clause
+                        .iter()
+                        .enumerate()
+                        .map(|(j, part)| {
+                            if j == 0 {
+                                part.clone()
+                            } else {
+                                part.to_datum()
+                            }
+                        })
+                        .collect(),
+                ),
+                (Expr::Keyword(Keyword::Cond), Expr::List(clause)) => {
+                    Expr::List(clause.iter().map(Expr::to_datum).collect())
+                }
+                (
+                    Expr::Keyword(Keyword::Let | Keyword::LetStar | Keyword::Letrec),
+                    Expr::List(bindings),
+                ) if i == 0 => Expr::List(
+                    bindings
+                        .iter()
+                        .map(|binding| match binding {
+                            Expr::List(parts) => {
+                                Expr::List(parts.iter().map(Expr::to_datum).collect())
+                            }
+                            binding => binding.to_datum(),
+                        })
+                        .collect(),
+                ),
+                (_, operand) => operand.to_datum(),
+            });
+
+        Expr::List(
+            std::iter::once(operator.to_datum())
+                .chain(operands)
+                .collect(),
+        )
+    }
+
+    /// Whether the value counts as true in conditions: anything but #f, Φ and Ω.
+    pub fn is_truthy(&self) -> bool {
+        !matches!(
+            self,
+            Expr::Literal(Literal::Bool(false) | Literal::Nil) | Expr::Keyword(Keyword::Nil)
+        )
+    }
+
+    /// The source span of the expression, if it was read from source.
+    pub fn span(&self) -> Option<Span> {
+        match self {
+            Expr::Expr { span, .. } | Expr::Var { span, .. } => *span,
+            _ => None,
+        }
     }
 }
 
@@ -52,111 +254,468 @@ macro_rules! token {
     ($tokens:expr, $position:expr) => {
         match $tokens.get($position) {
             Some(&(t, _)) => Ok(t),
-            None => Err(format!(
-                "Cannot parse an empty string [{}/{}]",
-                $position,
-                $tokens.len()
+            None => Err(EvalError::parse(
+                format!(
+                    "Cannot parse an empty string [{}/{}]",
+                    $position,
+                    $tokens.len()
+                ),
+                $tokens.last().map(|&(_, span)| *span),
             )),
         }
     };
 }
 
-fn parse_keyword(tokens: &Vec<&Token>, position: usize) -> Result<ParseResult<Expr>, String> {
+fn span(tokens: &[&Token], position: usize) -> Option<Span> {
+    tokens.get(position).map(|&(_, span)| *span)
+}
+
+fn parse_keyword(tokens: &Vec<&Token>, position: usize) -> Result<ParseResult<Expr>, EvalError> {
     match token!(tokens, position)? {
         Lexem::Keyword(keyword) => Ok(ParseResult {
             expr: Expr::Keyword(*keyword),
             next_position: position + 1,
         }),
-        token => Err(format!("parse_keyword cannot parse {:?}", token)),
+        token => Err(EvalError::parse(
+            format!("parse_keyword cannot parse {:?}", token),
+            span(tokens, position),
+        )),
     }
 }
 
-fn parse_literal(tokens: &Vec<&Token>, position: usize) -> Result<ParseResult<Expr>, String> {
+fn parse_literal(tokens: &Vec<&Token>, position: usize) -> Result<ParseResult<Expr>, EvalError> {
     match token!(tokens, position)? {
         Lexem::Literal(lit) => Ok(ParseResult {
             expr: Expr::Literal(lit.clone()),
             next_position: position + 1,
         }),
-        token => Err(format!("parse_literal cannot parse {:?}", token)),
+        token => Err(EvalError::parse(
+            format!("parse_literal cannot parse {:?}", token),
+            span(tokens, position),
+        )),
     }
 }
 
 fn parse_parenthesis_open(
     tokens: &Vec<&Token>,
     position: usize,
-) -> Result<ParseResult<Lexem>, String> {
+) -> Result<ParseResult<Lexem>, EvalError> {
     match token!(tokens, position)? {
         Lexem::ParenthesisOpen => Ok(ParseResult {
             expr: Lexem::ParenthesisOpen,
             next_position: position + 1,
         }),
-        token => Err(format!("parse_parenthesis_open cannot parse {:?}", token)),
-    }
-}
-
-fn parse_parenthesis_close(
-    tokens: &Vec<&Token>,
-    position: usize,
-) -> Result<ParseResult<Lexem>, String> {
-    match token!(tokens, position)? {
-        Lexem::ParenthesisClose => Ok(ParseResult {
-            expr: Lexem::ParenthesisClose,
-            next_position: position + 1,
-        }),
-        token => Err(format!("parse_parenthesis_close cannot parse {:?}", token)),
+        token => Err(EvalError::parse(
+            format!("parse_parenthesis_open cannot parse {:?}", token),
+            span(tokens, position),
+        )),
     }
 }
 
-fn parse_var(tokens: &Vec<&Token>, position: usize) -> Result<ParseResult<Expr>, String> {
+fn parse_var(tokens: &Vec<&Token>, position: usize) -> Result<ParseResult<Expr>, EvalError> {
     match token!(tokens, position)? {
         Lexem::Identifier(name) => Ok(ParseResult {
             expr: Expr::Var {
                 name: name.to_owned(),
+                span: span(tokens, position),
             },
             next_position: position + 1,
         }),
-        token => Err(format!("parse_var cannot parse {:?}", token)),
+        token => Err(EvalError::parse(
+            format!("parse_var cannot parse {:?}", token),
+            span(tokens, position),
+        )),
     }
 }
 
-fn parse_operator(tokens: &Vec<&Token>, position: usize) -> Result<ParseResult<Expr>, String> {
+fn parse_operator(tokens: &Vec<&Token>, position: usize) -> Result<ParseResult<Expr>, EvalError> {
     parse_var(tokens, position)
         .or_else(|_| parse_keyword(tokens, position))
         .or_else(|_| parse_expression(tokens, position))
-        .or_else(|_| {
-            Err(format!(
-                "parse_operator cannot parse {:?}",
-                tokens.get(position).unwrap()
-            ))
+        .map_err(|_| {
+            EvalError::parse(
+                format!("parse_operator cannot parse {:?}", tokens.get(position)),
+                span(tokens, position),
+            )
         })
 }
 
-fn parse_list(tokens: &Vec<&Token>, position: usize) -> Result<ParseResult<Vec<Expr>>, String> {
+type Parser<T> = fn(&Vec<&Token>, usize) -> Result<ParseResult<T>, EvalError>;
+
+fn parse_list(tokens: &Vec<&Token>, position: usize) -> Result<ParseResult<Vec<Expr>>, EvalError> {
+    parse_list_of(tokens, position, parse)
+}
+
+/// Parses items with `item` up to and including the closing parenthesis.
+fn parse_list_of(
+    tokens: &Vec<&Token>,
+    position: usize,
+    item: Parser<Expr>,
+) -> Result<ParseResult<Vec<Expr>>, EvalError> {
+    parse_until(tokens, position, item, Lexem::ParenthesisClose)
+}
+
+/// Parses items with `item` up to and including the `close` token.
+fn parse_until(
+    tokens: &Vec<&Token>,
+    position: usize,
+    item: Parser<Expr>,
+    close: Lexem,
+) -> Result<ParseResult<Vec<Expr>>, EvalError> {
     let mut expressions = vec![];
     let mut position = position;
 
     while let Ok(token) = token!(tokens, position) {
-        match token {
-            Lexem::ParenthesisClose => break,
-            _ => {
-                let ParseResult {
-                    expr,
-                    next_position,
-                } = parse(tokens, position)?;
+        if *token == close {
+            break;
+        }
+
+        let ParseResult {
+            expr,
+            next_position,
+        } = item(tokens, position)?;
+
+        position = next_position;
+        expressions.push(expr);
+    }
+
+    match token!(tokens, position)? {
+        token if *token == close => Ok(ParseResult {
+            expr: expressions,
+            next_position: position + 1,
+        }),
+        token => Err(EvalError::parse(
+            format!("parse_until cannot parse {:?}", token),
+            span(tokens, position),
+        )),
+    }
+}
+
+/// Parses the keys and values of `{k v ...}` with `item`.
+fn parse_braces(
+    tokens: &Vec<&Token>,
+    position: usize,
+    item: Parser<Expr>,
+) -> Result<ParseResult<Vec<Expr>>, EvalError> {
+    match token!(tokens, position)? {
+        Lexem::BraceOpen => {
+            let result = parse_until(tokens, position + 1, item, Lexem::BraceClose)?;
 
-                position = next_position;
-                expressions.push(expr);
+            if result.expr.len() % 2 == 1 {
+                return Err(EvalError::parse(
+                    "A map needs a value for every key".to_string(),
+                    span(tokens, position)
+                        .zip(span(tokens, result.next_position - 1))
+                        .map(|(start, end)| start.to(end)),
+                ));
             }
+
+            Ok(result)
         }
+        token => Err(EvalError::parse(
+            format!("parse_braces cannot parse {:?}", token),
+            span(tokens, position),
+        )),
+    }
+}
+
+/// Parses `{k v ...}` as code, building the map from the values of the keys and values.
+fn parse_map(tokens: &Vec<&Token>, position: usize) -> Result<ParseResult<Expr>, EvalError> {
+    let ParseResult {
+        expr: operands,
+        next_position,
+    } = parse_braces(tokens, position, parse)?;
+
+    Ok(ParseResult {
+        expr: Expr::Expr {
+            operator: Box::new(Expr::Primitive(Primitive::HashMap)),
+            operands,
+            span: span(tokens, position)
+                .zip(span(tokens, next_position - 1))
+                .map(|(start, end)| start.to(end)),
+        },
+        next_position,
+    })
+}
+
+/// Parses `{k v ...}` as data, into the map of the quoted keys and values.
+fn parse_map_datum(tokens: &Vec<&Token>, position: usize) -> Result<ParseResult<Expr>, EvalError> {
+    let ParseResult {
+        expr: items,
+        next_position,
+    } = parse_braces(tokens, position, parse_datum)?;
+
+    let mut map = Map::new();
+    for entry in items.chunks(2) {
+        let key = entry[0].quoted();
+
+        map = match Key::new(&key) {
+            Some(key) => map.insert(key, entry[1].quoted()),
+            None => {
+                return Err(EvalError::parse(
+                    format!("{} cannot be a map key", key),
+                    entry[0].span().or_else(|| span(tokens, position)),
+                ))
+            }
+        };
+    }
+
+    Ok(ParseResult {
+        expr: Expr::Map(map),
+        next_position,
+    })
+}
+
+/// Parses the items of `[a b ...]` with `item`.
+fn parse_brackets(
+    tokens: &Vec<&Token>,
+    position: usize,
+    item: Parser<Expr>,
+) -> Result<ParseResult<Vec<Expr>>, EvalError> {
+    match token!(tokens, position)? {
+        Lexem::BracketOpen => parse_until(tokens, position + 1, item, Lexem::BracketClose),
+        token => Err(EvalError::parse(
+            format!("parse_brackets cannot parse {:?}", token),
+            span(tokens, position),
+        )),
     }
+}
+
+/// Parses `[a b ...]` as code, building the vector from the values of the items.
+fn parse_vector(tokens: &Vec<&Token>, position: usize) -> Result<ParseResult<Expr>, EvalError> {
+    let ParseResult {
+        expr: operands,
+        next_position,
+    } = parse_brackets(tokens, position, parse)?;
 
     Ok(ParseResult {
-        expr: expressions,
-        next_position: position + 1,
+        expr: Expr::Expr {
+            operator: Box::new(Expr::Primitive(Primitive::Vector)),
+            operands,
+            span: span(tokens, position)
+                .zip(span(tokens, next_position - 1))
+                .map(|(start, end)| start.to(end)),
+        },
+        next_position,
     })
 }
 
-fn parse_expression(tokens: &Vec<&Token>, position: usize) -> Result<ParseResult<Expr>, String> {
+/// Parses `[a b ...]` as data, into the vector of the quoted items.
+fn parse_vector_datum(
+    tokens: &Vec<&Token>,
+    position: usize,
+) -> Result<ParseResult<Expr>, EvalError> {
+    let ParseResult {
+        expr: items,
+        next_position,
+    } = parse_brackets(tokens, position, parse_datum)?;
+
+    Ok(ParseResult {
+        expr: Expr::Vector(items.iter().map(Expr::quoted).collect()),
+        next_position,
+    })
+}
+
+/// Parses reader syntax such as `'datum` into `(quote datum)`. The operand is read as data,
+/// unquoted parts being turned back into code when they are evaluated.
+fn parse_prefix(tokens: &Vec<&Token>, position: usize) -> Result<ParseResult<Expr>, EvalError> {
+    match token!(tokens, position)? {
+        Lexem::Prefix(keyword) => {
+            let ParseResult {
+                expr: operand,
+                next_position,
+            } = parse_datum(tokens, position + 1)?;
+
+            Ok(ParseResult {
+                expr: Expr::Expr {
+                    operator: Box::new(Expr::Keyword(*keyword)),
+                    operands: vec![operand],
+                    span: span(tokens, position)
+                        .zip(span(tokens, next_position - 1))
+                        .map(|(start, end)| start.to(end)),
+                },
+                next_position,
+            })
+        }
+        token => Err(EvalError::parse(
+            format!("parse_prefix cannot parse {:?}", token),
+            span(tokens, position),
+        )),
+    }
+}
+
+/// Parses a form as data: parenthesized forms become lists, whatever their first element.
+fn parse_datum(tokens: &Vec<&Token>, position: usize) -> Result<ParseResult<Expr>, EvalError> {
+    parse_keyword(tokens, position)
+        .or_else(|_| parse_literal(tokens, position))
+        .or_else(|_| parse_var(tokens, position))
+        .or_else(|_| {
+            // Reader syntax is kept as a list, e.g. `(quote datum)`.
+            let ParseResult {
+                expr: datum,
+                next_position,
+            } = parse_prefix(tokens, position)?;
+
+            match datum {
+                Expr::Expr {
+                    operator, operands, ..
+                } => Ok(ParseResult {
+                    expr: Expr::List(std::iter::once(*operator).chain(operands).collect()),
+                    next_position,
+                }),
+                datum => Ok(ParseResult {
+                    expr: datum,
+                    next_position,
+                }),
+            }
+        })
+        .or_else(|_: EvalError| {
+            match token!(tokens, position)? {
+                Lexem::BraceOpen => return parse_map_datum(tokens, position),
+                Lexem::BracketOpen => return parse_vector_datum(tokens, position),
+                _ => {}
+            }
+
+            let ParseResult { next_position, .. } = parse_parenthesis_open(tokens, position)?;
+            let ParseResult {
+                expr: items,
+                next_position,
+            } = parse_list_of(tokens, next_position, parse_datum)?;
+
+            Ok(ParseResult {
+                expr: Expr::List(items.into_iter().collect()),
+                next_position,
+            })
+        })
+}
+
+/// Parses a `match` clause, `(pattern body)` or `(pattern when guard body)`, into a list
+/// of its parts, the pattern being read as data.
+fn parse_match_clause(
+    tokens: &Vec<&Token>,
+    position: usize,
+) -> Result<ParseResult<Expr>, EvalError> {
+    let ParseResult { next_position, .. } = parse_parenthesis_open(tokens, position)?;
+    let ParseResult {
+        expr: pattern,
+        next_position,
+    } = parse_datum(tokens, next_position)?;
+    let ParseResult {
+        expr: rest,
+        next_position,
+    } = parse_list(tokens, next_position)?;
+
+    Ok(ParseResult {
+        expr: Expr::List(std::iter::once(pattern).chain(rest).collect()),
+        next_position,
+    })
+}
+
+/// Parses a `cond` clause, `(test body)`, into a list of its parts.
+fn parse_clause(tokens: &Vec<&Token>, position: usize) -> Result<ParseResult<Expr>, EvalError> {
+    let ParseResult { next_position, .. } = parse_parenthesis_open(tokens, position)?;
+    let ParseResult {
+        expr: parts,
+        next_position,
+    } = parse_list(tokens, next_position)?;
+
+    Ok(ParseResult {
+        expr: Expr::List(parts.into_iter().collect()),
+        next_position,
+    })
+}
+
+/// Parses the operands of `match`: the scrutinee followed by the clauses.
+fn parse_match(tokens: &Vec<&Token>, position: usize) -> Result<ParseResult<Vec<Expr>>, EvalError> {
+    let ParseResult {
+        expr: scrutinee,
+        next_position,
+    } = parse(tokens, position)?;
+    let ParseResult {
+        expr: clauses,
+        next_position,
+    } = parse_list_of(tokens, next_position, parse_match_clause)?;
+
+    Ok(ParseResult {
+        expr: std::iter::once(scrutinee).chain(clauses).collect(),
+        next_position,
+    })
+}
+
+/// Parses the operands of `let`, `let*` and `letrec`: the bindings, `((name value) ...)`,
+/// followed by the body.
+fn parse_let(tokens: &Vec<&Token>, position: usize) -> Result<ParseResult<Vec<Expr>>, EvalError> {
+    let ParseResult { next_position, .. } = parse_parenthesis_open(tokens, position)?;
+    let ParseResult {
+        expr: bindings,
+        next_position,
+    } = parse_list_of(tokens, next_position, parse_clause)?;
+    let ParseResult {
+        expr: body,
+        next_position,
+    } = parse_list(tokens, next_position)?;
+
+    Ok(ParseResult {
+        expr: std::iter::once(Expr::List(bindings.into_iter().collect()))
+            .chain(body)
+            .collect(),
+        next_position,
+    })
+}
+
+/// Parses the operands of `λ`: the parameters, where `()` takes no arguments, followed by
+/// the body.
+fn parse_lambda(
+    tokens: &Vec<&Token>,
+    position: usize,
+) -> Result<ParseResult<Vec<Expr>>, EvalError> {
+    let (parameters, next_position) = match token!(tokens, position)? {
+        Lexem::ParenthesisClose => return parse_list(tokens, position),
+        // The parameters are read as they are written, without flattening `((a) b)` into
+        // `(a b)` or reading `(0 x)` as `(ι 0 x)`, so that invalid ones are reported.
+        Lexem::ParenthesisOpen => {
+            let ParseResult {
+                expr: parameters,
+                next_position,
+            } = parse_list(tokens, position + 1)?;
+            let span = span(tokens, position)
+                .zip(span(tokens, next_position - 1))
+                .map(|(start, end)| start.to(end));
+            let mut parameters = parameters.into_iter();
+
+            match parameters.next() {
+                Some(operator) => (
+                    Expr::Expr {
+                        operator: Box::new(operator),
+                        operands: parameters.collect(),
+                        span,
+                    },
+                    next_position,
+                ),
+                None => (Expr::List(List::nil()), next_position),
+            }
+        }
+        _ => {
+            let ParseResult {
+                expr,
+                next_position,
+            } = parse(tokens, position)?;
+
+            (expr, next_position)
+        }
+    };
+    let ParseResult {
+        expr: body,
+        next_position,
+    } = parse_list(tokens, next_position)?;
+
+    Ok(ParseResult {
+        expr: std::iter::once(parameters).chain(body).collect(),
+        next_position,
+    })
+}
+
+fn parse_expression(tokens: &Vec<&Token>, position: usize) -> Result<ParseResult<Expr>, EvalError> {
     parse_parenthesis_open(tokens, position).and_then(|r| {
         match parse_operator(tokens, r.next_position) {
             Ok(ParseResult {
@@ -166,29 +725,48 @@ fn parse_expression(tokens: &Vec<&Token>, position: usize) -> Result<ParseResult
                 let ParseResult {
                     expr: operands,
                     next_position,
-                } = parse_list(tokens, next_position)?;
+                } = match operator {
+                    Expr::Keyword(Keyword::Match) => parse_match(tokens, next_position)?,
+                    Expr::Keyword(Keyword::Cond) => {
+                        parse_list_of(tokens, next_position, parse_clause)?
+                    }
+                    Expr::Keyword(Keyword::Let | Keyword::LetStar | Keyword::Letrec) => {
+                        parse_let(tokens, next_position)?
+                    }
+                    Expr::Keyword(Keyword::Lambda) => parse_lambda(tokens, next_position)?,
+                    Expr::Keyword(Keyword::Quote | Keyword::Quasiquote) => {
+                        parse_list_of(tokens, next_position, parse_datum)?
+                    }
+                    _ => parse_list(tokens, next_position)?,
+                };
+                let span = span(tokens, position)
+                    .zip(span(tokens, next_position - 1))
+                    .map(|(start, end)| start.to(end));
 
                 match &operator {
                     Expr::Expr {
                         operator: op,
                         operands: op_operands,
-                    } => {
+                        ..
+                    } if !matches!(op.as_ref(), Expr::Keyword(keyword) if keyword.has_body()) => {
                         let mut new_operands = vec![];
-                        new_operands.extend(op_operands.into_iter().map(|x| x.clone()));
-                        new_operands.extend(operands.into_iter());
+                        new_operands.extend(op_operands.iter().cloned());
+                        new_operands.extend(operands);
 
                         Ok(ParseResult {
                             expr: Expr::Expr {
                                 operator: op.clone(),
                                 operands: new_operands,
+                                span,
                             },
-                            next_position: next_position,
+                            next_position,
                         })
                     }
                     _ => Ok(ParseResult {
                         expr: Expr::Expr {
                             operator: Box::new(operator),
                             operands,
+                            span,
                         },
                         next_position,
                     }),
@@ -207,6 +785,9 @@ fn parse_expression(tokens: &Vec<&Token>, position: usize) -> Result<ParseResult
                         expr: Expr::Expr {
                             operator: Box::new(Expr::Keyword(Keyword::Id)),
                             operands,
+                            span: span(tokens, position)
+                                .zip(span(tokens, next_position - 1))
+                                .map(|(start, end)| start.to(end)),
                         },
                         next_position,
                     })
@@ -216,33 +797,91 @@ fn parse_expression(tokens: &Vec<&Token>, position: usize) -> Result<ParseResult
     })
 }
 
-fn parse(tokens: &Vec<&Token>, position: usize) -> Result<ParseResult<Expr>, String> {
+fn parse(tokens: &Vec<&Token>, position: usize) -> Result<ParseResult<Expr>, EvalError> {
     parse_keyword(tokens, position)
         .or_else(|_| parse_literal(tokens, position))
         .or_else(|_| parse_var(tokens, position))
-        .or_else(|_| parse_expression(tokens, position))
+        .or_else(|_| parse_prefix(tokens, position))
+        .or_else(|_| match token!(tokens, position)? {
+            Lexem::BraceOpen => parse_map(tokens, position),
+            Lexem::BracketOpen => parse_vector(tokens, position),
+            _ => parse_expression(tokens, position),
+        })
 }
 
-pub fn run_parser(tokens: &Vec<&Token>) -> Result<ParseResult<Expr>, String> {
-    if tokens.is_empty() {
-        Ok(ParseResult {
-            expr: Expr::Literal(Literal::Nil),
-            next_position: 1,
-        })
-    } else {
-        parse(tokens, 0)
+/// How deeply forms may be nested, which bounds the stack used by the parser and by the
+/// functions walking what it returns.
+pub const MAX_NESTING: usize = 1000;
+
+/// Rejects tokens nesting forms more than `MAX_NESTING` deep. Every parenthesis, brace or
+/// bracket nests the forms up to its closing one, and every prefix the datum following it.
+fn check_nesting(tokens: &[&Token]) -> Result<(), EvalError> {
+    // Whether each enclosing form is opened by a prefix rather than a parenthesis.
+    let mut enclosing: Vec<bool> = vec![];
+
+    for (lexem, span) in tokens {
+        let complete = match lexem {
+            Lexem::ParenthesisOpen | Lexem::BraceOpen | Lexem::BracketOpen => {
+                enclosing.push(false);
+                false
+            }
+            Lexem::Prefix(_) => {
+                enclosing.push(true);
+                false
+            }
+            Lexem::ParenthesisClose | Lexem::BraceClose | Lexem::BracketClose => {
+                enclosing.pop();
+                true
+            }
+            _ => true,
+        };
+
+        if enclosing.len() > MAX_NESTING {
+            return Err(EvalError::parse(
+                format!("Forms are nested more than {} deep", MAX_NESTING),
+                Some(*span),
+            ));
+        }
+
+        // A complete datum completes the prefixes waiting for it.
+        while complete && enclosing.last() == Some(&true) {
+            enclosing.pop();
+        }
+    }
+
+    Ok(())
+}
+
+/// Parses every top-level form of a program, in order.
+pub fn parse_program(source: &str) -> Result<Vec<Expr>, EvalError> {
+    let mut lexer = Lexer::new();
+
+    lexer.lex(source)?;
+
+    let tokens = lexer.lexems();
+    check_nesting(&tokens)?;
+
+    let mut forms = vec![];
+    let mut position = 0;
+
+    while position < tokens.len() {
+        let ParseResult {
+            expr,
+            next_position,
+        } = parse(&tokens, position)?;
+
+        position = next_position;
+        forms.push(expr);
     }
+
+    Ok(forms)
 }
 
 #[macro_export]
 macro_rules! parse {
     ($src:expr) => {{
-        let mut lexer = crate::lexer::Lexer::new();
-
-        lexer.lex(&$src.to_string()).unwrap();
-
-        let r = crate::parser::run_parser(&lexer.lexems()).unwrap();
-
-        r.expr().clone()
+        $crate::parser::parse_program(&$src.to_string())
+            .unwrap()
+            .remove(0)
     }};
 }
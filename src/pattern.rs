@@ -0,0 +1,69 @@
+use crate::{
+    closure::REST_SEPARATOR, keywords::Keyword, list::List, literal::Literal, parser::Expr,
+    symbol::Symbol,
+};
+
+/// Matches a value against a `match` pattern, collecting the variables it binds.
+///
+/// Patterns are `_`, variables, literals, `Ω`, quoted identifiers matching their symbol
+/// and lists of patterns, which may end with `. rest` to bind the remaining elements.
+pub fn matches(pattern: &Expr, value: &Expr, bindings: &mut Vec<(String, Expr)>) -> bool {
+    match pattern {
+        Expr::Keyword(Keyword::Ignore) => true,
+        Expr::Keyword(Keyword::Nil) | Expr::Literal(Literal::Nil) => matches!(
+            value,
+            Expr::Keyword(Keyword::Nil) | Expr::Literal(Literal::Nil)
+        ),
+        Expr::Var { name, .. } => {
+            bindings.push((name.clone(), value.clone()));
+            true
+        }
+        Expr::Literal(expected) => matches!(value, Expr::Literal(actual) if actual == expected),
+        Expr::List(patterns) if quoted(patterns).is_some() => match (quoted(patterns), value) {
+            (Some(expected), Expr::Literal(Literal::Symbol(actual))) => expected == *actual,
+            _ => false,
+        },
+        Expr::List(patterns) => match value {
+            Expr::List(values) => matches_list(patterns, values, bindings),
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
+/// The symbol of a `'name` pattern.
+fn quoted(pattern: &List) -> Option<Symbol> {
+    match pattern.iter().collect::<Vec<&Expr>>()[..] {
+        [Expr::Keyword(Keyword::Quote), Expr::Var { name, .. }] => Some(Symbol::new(name)),
+        _ => None,
+    }
+}
+
+fn matches_list(patterns: &List, values: &List, bindings: &mut Vec<(String, Expr)>) -> bool {
+    let mut patterns = patterns.iter();
+    let mut values = values.clone();
+
+    while let Some(pattern) = patterns.next() {
+        if let Expr::Var { name, .. } = pattern {
+            if name == REST_SEPARATOR {
+                return match (patterns.next(), patterns.next()) {
+                    (Some(rest), None) => matches(rest, &Expr::List(values), bindings),
+                    _ => false,
+                };
+            }
+        }
+
+        let (head, tail) = match (values.head(), values.tail()) {
+            (Some(head), Some(tail)) => (head.clone(), tail.clone()),
+            _ => return false,
+        };
+
+        if !matches(pattern, &head, bindings) {
+            return false;
+        }
+
+        values = tail;
+    }
+
+    values.is_empty()
+}
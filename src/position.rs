@@ -1,4 +1,6 @@
-#[derive(Debug, Clone, Copy)]
+use std::fmt::Display;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
 pub struct Position {
     row: usize,
     col: usize,
@@ -9,6 +11,14 @@ impl Position {
         Self { row, col }
     }
 
+    pub fn row(&self) -> usize {
+        self.row
+    }
+
+    pub fn col(&self) -> usize {
+        self.col
+    }
+
     pub fn next_col(&mut self) -> Self {
         self.col += 1;
 
@@ -22,3 +32,40 @@ impl Position {
         *self
     }
 }
+
+impl Display for Position {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_fmt(format_args!("{}:{}", self.row + 1, self.col + 1))
+    }
+}
+
+/// A half-open range of source positions, `end` being one past the last character.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Span {
+    start: Position,
+    end: Position,
+}
+
+impl Span {
+    pub fn new(start: Position, end: Position) -> Self {
+        Self { start, end }
+    }
+
+    pub fn start(&self) -> Position {
+        self.start
+    }
+
+    pub fn end(&self) -> Position {
+        self.end
+    }
+
+    pub fn to(&self, other: Span) -> Self {
+        Self::new(self.start, other.end)
+    }
+}
+
+impl Display for Span {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_fmt(format_args!("{}", self.start))
+    }
+}
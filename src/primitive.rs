@@ -0,0 +1,771 @@
+use std::{cmp::Ordering, fmt::Display, rc::Rc};
+
+use crate::{
+    cell::Cell,
+    error::EvalError,
+    list::List,
+    literal::Literal,
+    map::{Key, Map},
+    number::Number,
+    parser::Expr,
+    symbol::Symbol,
+    vector::Vector,
+};
+
+/// Applies a function to arguments on behalf of higher-order primitives such as `map`.
+pub type Call<'a> = dyn FnMut(&Expr, Vec<Expr>) -> Result<Expr, EvalError> + 'a;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Primitive {
+    Add,            // 0+
+    Sub,            // 1+
+    Mul,            // 0+
+    Div,            // 1+
+    Mod,            // 2
+    Lt,             // 2+
+    Le,             // 2+
+    Eq,             // 2+
+    Gt,             // 2+
+    Ge,             // 2+
+    Min,            // 1+
+    Max,            // 1+
+    Abs,            // 1
+    Floor,          // 1
+    Not,            // 1
+    Cons,           // 2
+    Car,            // 1
+    Cdr,            // 1
+    List,           // 0+
+    Null,           // 1
+    Length,         // 1
+    Append,         // 0+
+    Map,            // 2
+    Filter,         // 2
+    Fold,           // 3
+    Concat,         // 0+
+    Substring,      // 2-3
+    Split,          // 2
+    Join,           // 2
+    Upper,          // 1
+    Lower,          // 1
+    ToString,       // 1
+    ParseNum,       // 1
+    StringEq,       // 2+
+    SymbolToString, // 1
+    StringToSymbol, // 1
+    Identical,      // 2
+    HashMap,        // 0+
+    Get,            // 2-3
+    Assoc,          // 3+
+    Dissoc,         // 1+
+    Keys,           // 1
+    Vals,           // 1
+    Contains,       // 2
+    Merge,          // 0+
+    Vector,         // 0+
+    Nth,            // 2
+    Push,           // 2+
+    Slice,          // 2-3
+    Len,            // 1
+    VectorToList,   // 1
+    Ref,            // 1
+    Deref,          // 1
+    SetRef,         // 2
+}
+
+impl Display for Primitive {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Primitive::Add => f.write_str("+"),
+            Primitive::Sub => f.write_str("-"),
+            Primitive::Mul => f.write_str("*"),
+            Primitive::Div => f.write_str("/"),
+            Primitive::Mod => f.write_str("mod"),
+            Primitive::Lt => f.write_str("<"),
+            Primitive::Le => f.write_str("<="),
+            Primitive::Eq => f.write_str("="),
+            Primitive::Gt => f.write_str(">"),
+            Primitive::Ge => f.write_str(">="),
+            Primitive::Min => f.write_str("min"),
+            Primitive::Max => f.write_str("max"),
+            Primitive::Abs => f.write_str("abs"),
+            Primitive::Floor => f.write_str("floor"),
+            Primitive::Not => f.write_str("not"),
+            Primitive::Cons => f.write_str("cons"),
+            Primitive::Car => f.write_str("car"),
+            Primitive::Cdr => f.write_str("cdr"),
+            Primitive::List => f.write_str("list"),
+            Primitive::Null => f.write_str("null?"),
+            Primitive::Length => f.write_str("length"),
+            Primitive::Append => f.write_str("append"),
+            Primitive::Map => f.write_str("map"),
+            Primitive::Filter => f.write_str("filter"),
+            Primitive::Fold => f.write_str("fold"),
+            Primitive::Concat => f.write_str("concat"),
+            Primitive::Substring => f.write_str("substring"),
+            Primitive::Split => f.write_str("split"),
+            Primitive::Join => f.write_str("join"),
+            Primitive::Upper => f.write_str("upper"),
+            Primitive::Lower => f.write_str("lower"),
+            Primitive::ToString => f.write_str("to-string"),
+            Primitive::ParseNum => f.write_str("parse-num"),
+            Primitive::StringEq => f.write_str("string=?"),
+            Primitive::SymbolToString => f.write_str("symbol->string"),
+            Primitive::StringToSymbol => f.write_str("string->symbol"),
+            Primitive::Identical => f.write_str("eq?"),
+            Primitive::HashMap => f.write_str("hash-map"),
+            Primitive::Get => f.write_str("get"),
+            Primitive::Assoc => f.write_str("assoc"),
+            Primitive::Dissoc => f.write_str("dissoc"),
+            Primitive::Keys => f.write_str("keys"),
+            Primitive::Vals => f.write_str("vals"),
+            Primitive::Contains => f.write_str("contains?"),
+            Primitive::Merge => f.write_str("merge"),
+            Primitive::Vector => f.write_str("vector"),
+            Primitive::Nth => f.write_str("nth"),
+            Primitive::Push => f.write_str("push"),
+            Primitive::Slice => f.write_str("slice"),
+            Primitive::Len => f.write_str("len"),
+            Primitive::VectorToList => f.write_str("vector->list"),
+            Primitive::Ref => f.write_str("ref"),
+            Primitive::Deref => f.write_str("deref"),
+            Primitive::SetRef => f.write_str("set-ref!"),
+        }
+    }
+}
+
+fn num(primitive: Primitive, expr: &Expr) -> Result<Number, EvalError> {
+    match expr {
+        Expr::Literal(Literal::Num(num)) => Ok(num.clone()),
+        e => Err(EvalError::type_error(
+            format!("{}", primitive),
+            "a number",
+            e,
+        )),
+    }
+}
+
+fn list(primitive: Primitive, expr: &Expr) -> Result<List, EvalError> {
+    match expr {
+        Expr::List(list) => Ok(list.clone()),
+        e => Err(EvalError::type_error(format!("{}", primitive), "a list", e)),
+    }
+}
+
+fn string(primitive: Primitive, expr: &Expr) -> Result<String, EvalError> {
+    match expr {
+        Expr::Literal(Literal::String(string)) => Ok(string.clone()),
+        e => Err(EvalError::type_error(
+            format!("{}", primitive),
+            "a string",
+            e,
+        )),
+    }
+}
+
+/// A position in `collection` of `length` items, inclusive of its end.
+fn index(
+    primitive: Primitive,
+    expr: &Expr,
+    collection: &str,
+    length: usize,
+) -> Result<usize, EvalError> {
+    match num(primitive, expr)? {
+        Number::Int(index) if index >= 0 && index as usize <= length => Ok(index as usize),
+        Number::Int(_) | Number::Big(_) => Err(EvalError::out_of_range(
+            format!("{}", primitive),
+            expr,
+            collection,
+            length,
+        )),
+        _ => Err(EvalError::type_error(
+            format!("{}", primitive),
+            "an integer",
+            expr,
+        )),
+    }
+}
+
+/// The start and end of a range of `collection`, the end defaulting to `length`.
+fn range(
+    primitive: Primitive,
+    start: &Expr,
+    end: Option<&Expr>,
+    collection: &str,
+    length: usize,
+) -> Result<(usize, usize), EvalError> {
+    let start = index(primitive, start, collection, length)?;
+    let end = match end {
+        Some(end) => index(primitive, end, collection, length)?,
+        None => length,
+    };
+
+    if start > end {
+        return Err(EvalError::failure(format!(
+            "{} start {} is after its end {}",
+            primitive, start, end
+        )));
+    }
+
+    Ok((start, end))
+}
+
+fn vector(primitive: Primitive, expr: &Expr) -> Result<Vector, EvalError> {
+    match expr {
+        Expr::Vector(vector) => Ok(vector.clone()),
+        e => Err(EvalError::type_error(
+            format!("{}", primitive),
+            "a vector",
+            e,
+        )),
+    }
+}
+
+fn cell(primitive: Primitive, expr: &Expr) -> Result<Cell, EvalError> {
+    match expr {
+        Expr::Cell(cell) => Ok(cell.clone()),
+        e => Err(EvalError::type_error(format!("{}", primitive), "a ref", e)),
+    }
+}
+
+fn map(primitive: Primitive, expr: &Expr) -> Result<Map, EvalError> {
+    match expr {
+        Expr::Map(map) => Ok(map.clone()),
+        e => Err(EvalError::type_error(format!("{}", primitive), "a map", e)),
+    }
+}
+
+fn key(primitive: Primitive, expr: &Expr) -> Result<Key, EvalError> {
+    Key::new(expr).ok_or_else(|| EvalError::type_error(format!("{}", primitive), "a map key", expr))
+}
+
+/// Adds alternating keys and values to a map.
+fn entries(primitive: Primitive, map: Map, arguments: &[Expr]) -> Result<Map, EvalError> {
+    if arguments.len() % 2 == 1 {
+        return Err(EvalError::failure(format!(
+            "{} expected a value for every key",
+            primitive
+        )));
+    }
+
+    arguments.chunks(2).try_fold(map, |map, entry| {
+        Ok(map.insert(key(primitive, &entry[0])?, entry[1].clone()))
+    })
+}
+
+/// Whether two values are the same: equal atoms, the empty list, or the same closure.
+fn identical(a: &Expr, b: &Expr) -> bool {
+    match (a, b) {
+        (Expr::Literal(a), Expr::Literal(b)) => a == b,
+        (Expr::Keyword(a), Expr::Keyword(b)) => a == b,
+        (Expr::Primitive(a), Expr::Primitive(b)) => a == b,
+        (Expr::Closure(a), Expr::Closure(b)) => Rc::ptr_eq(a, b),
+        (Expr::Cell(a), Expr::Cell(b)) => a.ptr_eq(b),
+        (Expr::List(a), Expr::List(b)) => a.is_empty() && b.is_empty(),
+        _ => false,
+    }
+}
+
+fn bool(value: bool) -> Expr {
+    Expr::Literal(Literal::Bool(value))
+}
+
+impl Primitive {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "+" => Some(Primitive::Add),
+            "-" => Some(Primitive::Sub),
+            "*" => Some(Primitive::Mul),
+            "/" => Some(Primitive::Div),
+            "mod" => Some(Primitive::Mod),
+            "<" => Some(Primitive::Lt),
+            "<=" => Some(Primitive::Le),
+            "=" => Some(Primitive::Eq),
+            ">" => Some(Primitive::Gt),
+            ">=" => Some(Primitive::Ge),
+            "min" => Some(Primitive::Min),
+            "max" => Some(Primitive::Max),
+            "abs" => Some(Primitive::Abs),
+            "floor" => Some(Primitive::Floor),
+            "not" => Some(Primitive::Not),
+            "cons" => Some(Primitive::Cons),
+            "car" => Some(Primitive::Car),
+            "cdr" => Some(Primitive::Cdr),
+            "list" => Some(Primitive::List),
+            "null?" => Some(Primitive::Null),
+            "length" => Some(Primitive::Length),
+            "append" => Some(Primitive::Append),
+            "map" => Some(Primitive::Map),
+            "filter" => Some(Primitive::Filter),
+            "fold" => Some(Primitive::Fold),
+            "concat" => Some(Primitive::Concat),
+            "substring" => Some(Primitive::Substring),
+            "split" => Some(Primitive::Split),
+            "join" => Some(Primitive::Join),
+            "upper" => Some(Primitive::Upper),
+            "lower" => Some(Primitive::Lower),
+            "to-string" => Some(Primitive::ToString),
+            "parse-num" => Some(Primitive::ParseNum),
+            "string=?" => Some(Primitive::StringEq),
+            "symbol->string" => Some(Primitive::SymbolToString),
+            "string->symbol" => Some(Primitive::StringToSymbol),
+            "eq?" => Some(Primitive::Identical),
+            "hash-map" => Some(Primitive::HashMap),
+            "get" => Some(Primitive::Get),
+            "assoc" => Some(Primitive::Assoc),
+            "dissoc" => Some(Primitive::Dissoc),
+            "keys" => Some(Primitive::Keys),
+            "vals" => Some(Primitive::Vals),
+            "contains?" => Some(Primitive::Contains),
+            "merge" => Some(Primitive::Merge),
+            "vector" => Some(Primitive::Vector),
+            "nth" => Some(Primitive::Nth),
+            "push" => Some(Primitive::Push),
+            "slice" => Some(Primitive::Slice),
+            "len" => Some(Primitive::Len),
+            "vector->list" => Some(Primitive::VectorToList),
+            "ref" => Some(Primitive::Ref),
+            "deref" => Some(Primitive::Deref),
+            "set-ref!" => Some(Primitive::SetRef),
+            _ => None,
+        }
+    }
+
+    pub fn arity(&self) -> (usize, Option<usize>) {
+        match self {
+            Primitive::Add
+            | Primitive::Mul
+            | Primitive::List
+            | Primitive::Append
+            | Primitive::Concat
+            | Primitive::HashMap
+            | Primitive::Merge
+            | Primitive::Vector => (0, None),
+            Primitive::Sub
+            | Primitive::Div
+            | Primitive::Min
+            | Primitive::Max
+            | Primitive::Dissoc => (1, None),
+            Primitive::Lt
+            | Primitive::Le
+            | Primitive::Eq
+            | Primitive::Gt
+            | Primitive::Ge
+            | Primitive::StringEq => (2, None),
+            Primitive::Mod
+            | Primitive::Cons
+            | Primitive::Map
+            | Primitive::Filter
+            | Primitive::Split
+            | Primitive::Join
+            | Primitive::Identical
+            | Primitive::Contains
+            | Primitive::Nth
+            | Primitive::SetRef => (2, Some(2)),
+            Primitive::Push => (2, None),
+            Primitive::Substring | Primitive::Get | Primitive::Slice => (2, Some(3)),
+            Primitive::Assoc => (3, None),
+            Primitive::Fold => (3, Some(3)),
+            Primitive::Abs
+            | Primitive::Floor
+            | Primitive::Not
+            | Primitive::Car
+            | Primitive::Cdr
+            | Primitive::Null
+            | Primitive::Length
+            | Primitive::Upper
+            | Primitive::Lower
+            | Primitive::ToString
+            | Primitive::ParseNum
+            | Primitive::SymbolToString
+            | Primitive::StringToSymbol
+            | Primitive::Keys
+            | Primitive::Vals
+            | Primitive::Len
+            | Primitive::VectorToList
+            | Primitive::Ref
+            | Primitive::Deref => (1, Some(1)),
+        }
+    }
+
+    pub fn apply(&self, arguments: &[Expr], call: &mut Call) -> Result<Expr, EvalError> {
+        let (min, max) = self.arity();
+
+        if arguments.len() < min || max.is_some_and(|max| arguments.len() > max) {
+            return Err(EvalError::arity(
+                format!("{}", self),
+                match max {
+                    Some(max) if max > min => format!("{} or {}", min, max),
+                    Some(_) => format!("{}", min),
+                    None => format!("{} or more", min),
+                },
+                arguments.len(),
+            ));
+        }
+
+        match self {
+            Primitive::Not => Ok(bool(!arguments[0].is_truthy())),
+            Primitive::Identical => Ok(bool(identical(&arguments[0], &arguments[1]))),
+            Primitive::Ref => Ok(Expr::Cell(Cell::new(arguments[0].clone()))),
+            Primitive::Deref => Ok(cell(*self, &arguments[0])?.get()),
+            // Returns the value stored.
+            Primitive::SetRef => {
+                cell(*self, &arguments[0])?.set(arguments[1].clone());
+
+                Ok(arguments[1].clone())
+            }
+            Primitive::Cons
+            | Primitive::Car
+            | Primitive::Cdr
+            | Primitive::List
+            | Primitive::Null
+            | Primitive::Length
+            | Primitive::Append
+            | Primitive::Map
+            | Primitive::Filter
+            | Primitive::Fold => self.apply_list(arguments, call),
+            Primitive::Concat
+            | Primitive::Substring
+            | Primitive::Split
+            | Primitive::Join
+            | Primitive::Upper
+            | Primitive::Lower
+            | Primitive::ToString
+            | Primitive::ParseNum
+            | Primitive::StringEq
+            | Primitive::SymbolToString
+            | Primitive::StringToSymbol => self.apply_string(arguments),
+            Primitive::HashMap
+            | Primitive::Get
+            | Primitive::Assoc
+            | Primitive::Dissoc
+            | Primitive::Keys
+            | Primitive::Vals
+            | Primitive::Contains
+            | Primitive::Merge => self.apply_map(arguments),
+            Primitive::Vector
+            | Primitive::Nth
+            | Primitive::Push
+            | Primitive::Slice
+            | Primitive::Len
+            | Primitive::VectorToList => self.apply_vector(arguments),
+            _ => self.apply_numeric(arguments),
+        }
+    }
+
+    fn apply_list(&self, arguments: &[Expr], call: &mut Call) -> Result<Expr, EvalError> {
+        let result = match self {
+            Primitive::Cons => List::cons(arguments[0].clone(), list(*self, &arguments[1])?),
+            Primitive::Car | Primitive::Cdr => {
+                let list = list(*self, &arguments[0])?;
+
+                return match (self, list.head(), list.tail()) {
+                    (Primitive::Car, Some(head), _) => Ok(head.clone()),
+                    (Primitive::Cdr, _, Some(tail)) => Ok(Expr::List(tail.clone())),
+                    _ => Err(EvalError::failure(format!("{} of an empty list", self))),
+                };
+            }
+            Primitive::List => arguments.iter().cloned().collect(),
+            Primitive::Null => {
+                return Ok(bool(match &arguments[0] {
+                    Expr::List(list) => list.is_empty(),
+                    argument => !argument.is_truthy(),
+                }))
+            }
+            // Also counts the characters of a string, the entries of a map and the items of
+            // a vector.
+            Primitive::Length => {
+                let length = match &arguments[0] {
+                    Expr::Literal(Literal::String(string)) => string.chars().count(),
+                    Expr::Map(map) => map.len(),
+                    Expr::Vector(vector) => vector.len(),
+                    argument => list(*self, argument)?.iter().count(),
+                };
+
+                return Ok(Expr::Literal(Literal::Num(Number::Int(length as i64))));
+            }
+            Primitive::Append => {
+                let lists = arguments
+                    .iter()
+                    .map(|argument| list(*self, argument))
+                    .collect::<Result<Vec<List>, EvalError>>()?;
+
+                lists.iter().flat_map(|list| list.iter().cloned()).collect()
+            }
+            Primitive::Map => list(*self, &arguments[1])?
+                .iter()
+                .map(|item| call(&arguments[0], vec![item.clone()]))
+                .collect::<Result<List, EvalError>>()?,
+            Primitive::Filter => {
+                let mut items = vec![];
+
+                for item in list(*self, &arguments[1])?.iter() {
+                    if call(&arguments[0], vec![item.clone()])?.is_truthy() {
+                        items.push(item.clone());
+                    }
+                }
+
+                items.into_iter().collect()
+            }
+            Primitive::Fold => {
+                return list(*self, &arguments[2])?
+                    .iter()
+                    .try_fold(arguments[1].clone(), |accumulator, item| {
+                        call(&arguments[0], vec![accumulator, item.clone()])
+                    })
+            }
+            _ => unreachable!("{} is not a list primitive", self),
+        };
+
+        Ok(Expr::List(result))
+    }
+
+    fn apply_string(&self, arguments: &[Expr]) -> Result<Expr, EvalError> {
+        let result = match self {
+            Primitive::Concat => arguments
+                .iter()
+                .map(|argument| string(*self, argument))
+                .collect::<Result<String, EvalError>>()?,
+            Primitive::Substring => {
+                let chars = string(*self, &arguments[0])?.chars().collect::<Vec<char>>();
+                let (start, end) = range(
+                    *self,
+                    &arguments[1],
+                    arguments.get(2),
+                    "a string",
+                    chars.len(),
+                )?;
+
+                chars[start..end].iter().collect()
+            }
+            // An empty separator splits the string into its characters.
+            Primitive::Split => {
+                let (string, separator) =
+                    (string(*self, &arguments[0])?, string(*self, &arguments[1])?);
+                let parts = match separator.as_str() {
+                    "" => string.chars().map(String::from).collect::<Vec<String>>(),
+                    separator => string.split(separator).map(String::from).collect(),
+                };
+
+                return Ok(Expr::List(
+                    parts
+                        .into_iter()
+                        .map(|part| Expr::Literal(Literal::String(part)))
+                        .collect(),
+                ));
+            }
+            Primitive::Join => list(*self, &arguments[0])?
+                .iter()
+                .map(|item| string(*self, item))
+                .collect::<Result<Vec<String>, EvalError>>()?
+                .join(&string(*self, &arguments[1])?),
+            Primitive::Upper => string(*self, &arguments[0])?.to_uppercase(),
+            Primitive::Lower => string(*self, &arguments[0])?.to_lowercase(),
+            // Strings are kept as they are, without the quotes of their printed form.
+            Primitive::ToString => match &arguments[0] {
+                Expr::Literal(Literal::String(string)) => string.clone(),
+                Expr::Literal(Literal::Char(c)) => c.to_string(),
+                argument => format!("{}", argument),
+            },
+            // Gives Φ for strings which are not numbers, so they can be told apart with `if`.
+            Primitive::ParseNum => {
+                return Ok(Expr::Literal(
+                    Literal::num(string(*self, &arguments[0])?.trim()).unwrap_or(Literal::Nil),
+                ))
+            }
+            Primitive::StringEq => {
+                let strings = arguments
+                    .iter()
+                    .map(|argument| string(*self, argument))
+                    .collect::<Result<Vec<String>, EvalError>>()?;
+
+                return Ok(bool(strings.windows(2).all(|w| w[0] == w[1])));
+            }
+            Primitive::SymbolToString => match &arguments[0] {
+                Expr::Literal(Literal::Symbol(symbol)) => symbol.name().to_string(),
+                argument => {
+                    return Err(EvalError::type_error(
+                        format!("{}", self),
+                        "a symbol",
+                        argument,
+                    ))
+                }
+            },
+            Primitive::StringToSymbol => {
+                return Ok(Expr::Literal(Literal::Symbol(Symbol::new(&string(
+                    *self,
+                    &arguments[0],
+                )?))))
+            }
+            _ => unreachable!("{} is not a string primitive", self),
+        };
+
+        Ok(Expr::Literal(Literal::String(result)))
+    }
+
+    fn apply_map(&self, arguments: &[Expr]) -> Result<Expr, EvalError> {
+        let result = match self {
+            Primitive::HashMap => entries(*self, Map::new(), arguments)?,
+            Primitive::Get => {
+                let (map, key) = (map(*self, &arguments[0])?, key(*self, &arguments[1])?);
+
+                return Ok(match (map.get(&key), arguments.get(2)) {
+                    (Some(value), _) | (None, Some(value)) => value.clone(),
+                    (None, None) => Expr::Literal(Literal::Nil),
+                });
+            }
+            Primitive::Assoc => entries(*self, map(*self, &arguments[0])?, &arguments[1..])?,
+            Primitive::Dissoc => arguments[1..]
+                .iter()
+                .try_fold(map(*self, &arguments[0])?, |map, argument| {
+                    Ok::<_, EvalError>(map.remove(&key(*self, argument)?))
+                })?,
+            Primitive::Keys | Primitive::Vals => {
+                let map = map(*self, &arguments[0])?;
+
+                return Ok(Expr::List(
+                    map.iter()
+                        .map(|(key, value)| match self {
+                            Primitive::Keys => key.to_expr(),
+                            _ => value.clone(),
+                        })
+                        .collect(),
+                ));
+            }
+            Primitive::Contains => {
+                return Ok(bool(
+                    map(*self, &arguments[0])?.contains(&key(*self, &arguments[1])?),
+                ))
+            }
+            // Entries of later maps replace those of earlier ones.
+            Primitive::Merge => arguments.iter().try_fold(Map::new(), |merged, argument| {
+                Ok::<_, EvalError>(
+                    map(*self, argument)?
+                        .iter()
+                        .fold(merged, |merged, (key, value)| {
+                            merged.insert(key.clone(), value.clone())
+                        }),
+                )
+            })?,
+            _ => unreachable!("{} is not a map primitive", self),
+        };
+
+        Ok(Expr::Map(result))
+    }
+
+    fn apply_vector(&self, arguments: &[Expr]) -> Result<Expr, EvalError> {
+        let result = match self {
+            Primitive::Vector => arguments.iter().cloned().collect(),
+            Primitive::Nth => {
+                let vector = vector(*self, &arguments[0])?;
+
+                let index = index(*self, &arguments[1], "a vector", vector.len())?;
+
+                // `index` admits the end of the vector, which holds no item.
+                return vector.get(index).cloned().ok_or_else(|| {
+                    EvalError::out_of_range(
+                        format!("{}", self),
+                        &arguments[1],
+                        "a vector",
+                        vector.len(),
+                    )
+                });
+            }
+            Primitive::Push => arguments[1..]
+                .iter()
+                .fold(vector(*self, &arguments[0])?, |vector, item| {
+                    vector.push(item.clone())
+                }),
+            Primitive::Slice => {
+                let vector = vector(*self, &arguments[0])?;
+                let (start, end) = range(
+                    *self,
+                    &arguments[1],
+                    arguments.get(2),
+                    "a vector",
+                    vector.len(),
+                )?;
+
+                vector.slice(start, end)
+            }
+            Primitive::Len => {
+                return Ok(Expr::Literal(Literal::Num(Number::Int(
+                    vector(*self, &arguments[0])?.len() as i64,
+                ))))
+            }
+            Primitive::VectorToList => {
+                return Ok(Expr::List(
+                    vector(*self, &arguments[0])?.iter().cloned().collect(),
+                ))
+            }
+            _ => unreachable!("{} is not a vector primitive", self),
+        };
+
+        Ok(Expr::Vector(result))
+    }
+
+    fn apply_numeric(&self, arguments: &[Expr]) -> Result<Expr, EvalError> {
+        let nums = arguments
+            .iter()
+            .map(|argument| num(*self, argument))
+            .collect::<Result<Vec<Number>, EvalError>>()?;
+        let compare = |holds: fn(Ordering) -> bool| {
+            bool(
+                nums.windows(2)
+                    .all(|w| w[0].compare(&w[1]).is_some_and(holds)),
+            )
+        };
+
+        let result = match self {
+            Primitive::Add => nums.iter().fold(Number::Int(0), |u, x| u.add(x)),
+            Primitive::Mul => nums.iter().fold(Number::Int(1), |u, x| u.mul(x)),
+            Primitive::Sub if nums.len() == 1 => nums[0].neg(),
+            Primitive::Sub => nums[1..].iter().fold(nums[0].clone(), |u, x| u.sub(x)),
+            Primitive::Div => {
+                let (first, rest) = match nums.len() {
+                    1 => (Number::Int(1), &nums[..]),
+                    _ => (nums[0].clone(), &nums[1..]),
+                };
+
+                if rest.iter().any(Number::is_zero) {
+                    return Err(EvalError::failure(format!("{} division by zero", self)));
+                }
+
+                rest.iter().fold(first, |u, x| u.div(x))
+            }
+            Primitive::Mod => {
+                if nums[1].is_zero() {
+                    return Err(EvalError::failure(format!("{} division by zero", self)));
+                }
+
+                nums[0].rem_euclid(&nums[1])
+            }
+            Primitive::Lt => return Ok(compare(Ordering::is_lt)),
+            Primitive::Le => return Ok(compare(Ordering::is_le)),
+            Primitive::Eq => return Ok(compare(Ordering::is_eq)),
+            Primitive::Gt => return Ok(compare(Ordering::is_gt)),
+            Primitive::Ge => return Ok(compare(Ordering::is_ge)),
+            // NaN is ignored, as by `f64::min` and `f64::max`.
+            Primitive::Min | Primitive::Max => {
+                let keep = if *self == Primitive::Min {
+                    Ordering::is_lt
+                } else {
+                    Ordering::is_gt
+                };
+
+                nums[1..]
+                    .iter()
+                    .fold(nums[0].clone(), |u, x| match x.compare(&u) {
+                        Some(ordering) if keep(ordering) => x.clone(),
+                        None if u.compare(&u).is_none() => x.clone(),
+                        _ => u,
+                    })
+            }
+            Primitive::Abs => nums[0].abs(),
+            Primitive::Floor => nums[0].floor(),
+            _ => unreachable!("{} is not a numeric primitive", self),
+        };
+
+        Ok(Expr::Literal(Literal::Num(result)))
+    }
+}
@@ -1,43 +1,117 @@
 use std::{
     fs::File,
-    io::{self, BufRead, Read, Write},
+    io::{self, BufRead, IsTerminal, Read, Write},
 };
 
-use crate::{evaluator::Runtime, parse};
+use crate::{error::EvalError, evaluator::Runtime, lexer::Lexer, parser::parse_program};
 
+const PROMPT: &str = "sl> ";
+const CONTINUATION_PROMPT: &str = "..> ";
+
+/// Reads a line, or `None` at the end of input (Ctrl-D).
 fn read() -> Option<String> {
     let mut line = String::new();
     let stdin = std::io::stdin();
     match stdin.lock().read_line(&mut line) {
+        Ok(0) | Err(_) => None,
         Ok(_) => Some(line),
-        Err(_) => None,
     }
 }
 
-fn pr(result: String) {
-    print!("{}\nsl> ", result);
+fn prompt(prompt: &str) {
+    print!("{}", prompt);
     io::stdout().flush().unwrap();
 }
 
-fn ev(runtime: &mut Runtime, input: &String) -> bool {
-    match input.as_str() {
-        "exit" => return false,
-        _ if input.starts_with("load ") => {
-          let path = input[5..].to_string().trim().to_string();
-          match File::open(&path).as_mut() {
-            Err(err) => pr(format!("[error] {} ({})", err, path)),
-            Ok(f) => {
-                let mut buf = String::new();
+fn pr(result: String) {
+    println!("{}", result);
+    prompt(PROMPT);
+}
+
+/// Whether the input can be evaluated, or is still missing closing parentheses or quotes.
+fn is_complete(input: &str) -> bool {
+    let mut lexer = Lexer::new();
 
-                f.read_to_string(&mut buf).unwrap();
+    match lexer.lex(input) {
+        Ok(()) => !lexer.is_open(),
+        // Only an unterminated string fails at the end of the input; any other error
+        // will not be fixed by reading more of it.
+        Err(_) => !lexer.in_string(),
+    }
+}
 
-                return ev(runtime, &buf);
+/// The tag starting error messages, in bold red when written to a terminal.
+pub fn error_tag(colour: bool) -> &'static str {
+    if colour {
+        "[\x1b[1;91merror\x1b[0m]"
+    } else {
+        "[error]"
+    }
+}
+
+/// Renders an error followed by the offending source line with its span underlined.
+pub fn format_error(source: &str, err: &EvalError, colour: bool) -> String {
+    let message = format!("{} {err}", error_tag(colour));
+
+    match err.span {
+        Some(span) => match source.lines().nth(span.start().row()) {
+            Some(line) => {
+                let width = if span.end().row() == span.start().row() {
+                    span.end().col().saturating_sub(span.start().col()).max(1)
+                } else {
+                    line.chars()
+                        .count()
+                        .saturating_sub(span.start().col())
+                        .max(1)
+                };
+
+                format!(
+                    "{}\n  {}\n  {}{}",
+                    message,
+                    line,
+                    " ".repeat(span.start().col()),
+                    "^".repeat(width)
+                )
             }
-        }},
-        _ if !input.is_empty() => match runtime.eval(&parse!(input)) {
-            Ok(r) => pr(format!("{}", r)),
-            Err(err) => pr(format!("[\\e[1;91meerror\\e[0m] {err}")),
+            None => message,
         },
+        None => message,
+    }
+}
+
+fn ev(runtime: &mut Runtime, input: &str) -> bool {
+    match input {
+        "exit" => return false,
+        _ if input.starts_with("load ") => {
+            let path = input[5..].to_string().trim().to_string();
+            match File::open(&path).as_mut() {
+                Err(err) => pr(format!(
+                    "{} {} ({})",
+                    error_tag(io::stdout().is_terminal()),
+                    err,
+                    path
+                )),
+                Ok(f) => {
+                    let mut buf = String::new();
+
+                    match f.read_to_string(&mut buf) {
+                        Ok(_) => return ev(runtime, &buf),
+                        Err(err) => pr(format!(
+                            "{} {} ({})",
+                            error_tag(io::stdout().is_terminal()),
+                            err,
+                            path
+                        )),
+                    }
+                }
+            }
+        }
+        _ if !input.is_empty() => {
+            match parse_program(input).and_then(|program| runtime.eval_program(&program)) {
+                Ok(r) => pr(format!("{}", r)),
+                Err(err) => pr(format_error(input, &err, io::stdout().is_terminal())),
+            }
+        }
         _ => pr("".to_string()),
     };
 
@@ -45,15 +119,53 @@ fn ev(runtime: &mut Runtime, input: &String) -> bool {
 }
 
 pub fn run() {
-    pr("".to_string());
+    prompt(PROMPT);
 
     let mut runtime = Runtime::new();
+    let mut input = String::new();
 
-    loop {
-        let input = read().unwrap_or("".to_string());
+    while let Some(line) = read() {
+        input.push_str(&line);
+
+        if !is_complete(&input) {
+            prompt(CONTINUATION_PROMPT);
+            continue;
+        }
 
-        if !ev(&mut runtime, &input) {
-            break;
+        if !ev(&mut runtime, input.trim()) {
+            return;
         }
+
+        input.clear();
+    }
+
+    println!();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{format_error, is_complete};
+    use crate::parser::parse_program;
+
+    #[test]
+    fn continuation() {
+        assert!(is_complete("(+ 1 2)\n"));
+        assert!(is_complete("\n"));
+        assert!(!is_complete("(δ f (λ x x)\n"));
+        assert!(!is_complete("(δ f (λ x x)\n  (f 1\n"));
+        assert!(is_complete("(δ f (λ x x)\n  (f 1))\n"));
+        assert!(!is_complete("(ε println \"a (\n"));
+        assert!(is_complete("(ε println \"a (\nb\")\n"));
+        assert!(is_complete("(+ 1 2))\n"));
+        assert!(is_complete("(+ 1 a\"b\" 2\n"));
+    }
+
+    #[test]
+    fn error_colours() {
+        let err = parse_program("(+ 1 2))").unwrap_err();
+        let plain = format_error("(+ 1 2))", &err, false);
+        assert!(plain.starts_with("[error] 1:8: "));
+        assert!(!plain.contains('\x1b'));
+        assert!(format_error("(+ 1 2))", &err, true).starts_with("[\x1b[1;91merror\x1b[0m] 1:8: "));
     }
 }
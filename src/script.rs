@@ -0,0 +1,151 @@
+use std::{
+    fs,
+    io::{self, IsTerminal, Read},
+};
+
+use crate::{
+    evaluator::Runtime,
+    external,
+    parser::parse_program,
+    repl::{error_tag, format_error},
+};
+
+pub const USAGE: &str = "usage: sl [-q] [file | -e expression | -] [arguments...]
+
+  file           evaluate the program in file
+  -e expression  evaluate the expression
+  -              read the program from standard input
+  -q, --quiet    do not print the value of the program
+  -h, --help     print this message
+
+Without a program, sl starts the REPL, unless standard input is not a terminal.";
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Input {
+    Repl,
+    File(String),
+    Expression(String),
+    Stdin,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Options {
+    pub input: Input,
+    pub quiet: bool,
+    pub help: bool,
+    /// Passed on to the program, see `external::arguments`.
+    pub arguments: Vec<String>,
+}
+
+impl Options {
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Self, String> {
+        let mut options = Options {
+            input: Input::Repl,
+            quiet: false,
+            help: false,
+            arguments: vec![],
+        };
+        let mut args = args.into_iter();
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "-q" | "--quiet" => options.quiet = true,
+                "-h" | "--help" => options.help = true,
+                "-e" => match args.next() {
+                    Some(expression) => {
+                        options.input = Input::Expression(expression);
+                        break;
+                    }
+                    None => return Err("-e expects an expression".to_string()),
+                },
+                "-" => {
+                    options.input = Input::Stdin;
+                    break;
+                }
+                _ if arg.starts_with('-') => return Err(format!("unknown option '{}'", arg)),
+                _ => {
+                    options.input = Input::File(arg);
+                    break;
+                }
+            }
+        }
+
+        options.arguments.extend(args);
+
+        Ok(options)
+    }
+}
+
+fn read(input: &Input) -> Result<String, String> {
+    match input {
+        Input::File(path) => fs::read_to_string(path).map_err(|err| format!("{} ({})", err, path)),
+        Input::Expression(expression) => Ok(expression.clone()),
+        Input::Stdin => {
+            let mut source = String::new();
+
+            io::stdin()
+                .read_to_string(&mut source)
+                .map(|_| source)
+                .map_err(|err| err.to_string())
+        }
+        Input::Repl => Err("the REPL is not a script".to_string()),
+    }
+}
+
+/// Evaluates the program non-interactively, returning the exit status.
+pub fn run(options: Options) -> i32 {
+    let source = match read(&options.input) {
+        Ok(source) => source,
+        Err(err) => {
+            eprintln!("{} {}", error_tag(io::stderr().is_terminal()), err);
+            return 1;
+        }
+    };
+
+    let mut runtime = Runtime::new();
+    external::arguments(&mut runtime, options.arguments);
+
+    match parse_program(&source).and_then(|program| runtime.eval_program(&program)) {
+        Ok(value) => {
+            if !options.quiet {
+                println!("{}", value);
+            }
+
+            0
+        }
+        Err(err) => {
+            eprintln!(
+                "{}",
+                format_error(&source, &err, io::stderr().is_terminal())
+            );
+            1
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Input, Options};
+
+    fn parse(args: &[&str]) -> Result<Options, String> {
+        Options::parse(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn options() {
+        let options = parse(&["-q", "main.lisp", "-e", "x"]).unwrap();
+        assert_eq!(options.input, Input::File("main.lisp".to_string()));
+        assert!(options.quiet);
+        assert_eq!(options.arguments, vec!["-e", "x"]);
+
+        let options = parse(&["-e", "(+ 1 2)", "a"]).unwrap();
+        assert_eq!(options.input, Input::Expression("(+ 1 2)".to_string()));
+        assert!(!options.quiet);
+        assert_eq!(options.arguments, vec!["a"]);
+
+        assert_eq!(parse(&["-"]).unwrap().input, Input::Stdin);
+        assert_eq!(parse(&[]).unwrap().input, Input::Repl);
+        assert!(parse(&["-e"]).is_err());
+        assert!(parse(&["--verbose"]).is_err());
+    }
+}
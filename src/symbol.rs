@@ -0,0 +1,44 @@
+use std::{cell::RefCell, collections::HashSet, fmt::Display, rc::Rc};
+
+thread_local! {
+    static SYMBOLS: RefCell<HashSet<Rc<str>>> = RefCell::new(HashSet::new());
+}
+
+/// An interned name, the value of a quoted identifier. Symbols with the same name share
+/// their storage, so comparing them compares pointers.
+#[derive(Debug, Clone)]
+pub struct Symbol(Rc<str>);
+
+impl Symbol {
+    pub fn new(name: &str) -> Self {
+        SYMBOLS.with(|symbols| {
+            let mut symbols = symbols.borrow_mut();
+
+            match symbols.get(name) {
+                Some(symbol) => Symbol(symbol.clone()),
+                None => {
+                    let symbol: Rc<str> = Rc::from(name);
+                    symbols.insert(symbol.clone());
+
+                    Symbol(symbol)
+                }
+            }
+        })
+    }
+
+    pub fn name(&self) -> &str {
+        &self.0
+    }
+}
+
+impl PartialEq for Symbol {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Display for Symbol {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
@@ -0,0 +1,51 @@
+use std::{fmt::Display, rc::Rc};
+
+use crate::parser::Expr;
+
+/// An immutable array, indexed in constant time. Updates copy the items the vector shares
+/// with others, leaving those unchanged.
+#[derive(Debug, Clone, Default)]
+pub struct Vector(Rc<Vec<Expr>>);
+
+impl Vector {
+    pub fn get(&self, index: usize) -> Option<&Expr> {
+        self.0.get(index)
+    }
+
+    pub fn push(&self, item: Expr) -> Self {
+        let mut vector = self.clone();
+        Rc::make_mut(&mut vector.0).push(item);
+
+        vector
+    }
+
+    /// The items from `start` up to, but not including, `end`.
+    pub fn slice(&self, start: usize, end: usize) -> Self {
+        self.0[start..end].iter().cloned().collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, Expr> {
+        self.0.iter()
+    }
+}
+
+impl FromIterator<Expr> for Vector {
+    fn from_iter<T: IntoIterator<Item = Expr>>(iter: T) -> Self {
+        Vector(Rc::new(iter.into_iter().collect()))
+    }
+}
+
+impl Display for Vector {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let items = self
+            .iter()
+            .map(|item| format!("{}", item))
+            .collect::<Vec<String>>();
+
+        f.write_fmt(format_args!("[{}]", items.join(" ")))
+    }
+}